@@ -3,27 +3,46 @@
 use anyhow::{bail, Context};
 use clap::Parser;
 use landlock::RulesetStatus;
-use landlockconfig::{Config, ConfigFormat, OptionalConfig};
+use landlockconfig::{detected_abi, Config, ConfigFormat, OptionalConfig};
 use std::fs::File;
 use std::io::Read;
 use std::os::unix::process::CommandExt;
 use std::path::Path;
 use std::process::Command;
 
-// TODO: Add option to only validate JSON and/or actual syscalls
-//
-// TODO: Warn about unused access rights, which might indicate that the
-// configuration needs to be updated to leverage the latest Landlock access
-// rights.  Add an option to disable this warning.
 #[derive(Parser, Debug)]
 struct Args {
-    #[arg(short, long, required_unless_present = "toml")]
+    #[arg(short, long, required_unless_present_any = ["toml", "discover"])]
     json: Vec<String>,
-    #[arg(short, long, required_unless_present = "json")]
+    #[arg(short, long, required_unless_present_any = ["json", "discover"])]
     toml: Vec<String>,
+    /// Walk up from the current directory composing any `.landlock.json`
+    /// found at each level, so a repository-local file tightens a
+    /// home-directory default, and compose the result with any --json/--toml
+    /// paths given explicitly.
+    #[arg(long)]
+    discover: bool,
     #[arg(short, long)]
     debug: bool,
-    #[arg(required = true)]
+    /// Instead of executing the command, print the composed configuration
+    /// (before resolving variables and ABI) as JSON and exit.
+    #[arg(long)]
+    merge: bool,
+    /// Dry run: parse, compose, resolve and validate the configuration
+    /// (including that every `pathBeneath` parent exists), print a report,
+    /// then exit without calling `restrict_self()`/executing the command.
+    #[arg(long)]
+    check: bool,
+    /// Print the detected kernel ABI plus access rights the config handles
+    /// that the kernel can't enforce and rights the kernel supports that
+    /// the config never handles.
+    #[arg(long)]
+    report: bool,
+    /// Suppress the warning about access rights the kernel supports but the
+    /// configuration never handles.
+    #[arg(long)]
+    no_unused_warning: bool,
+    #[arg(required_unless_present_any = ["merge", "check"])]
     command: Vec<String>,
 }
 
@@ -70,9 +89,21 @@ fn main() -> anyhow::Result<()> {
         full_config.compose(&config);
     }
 
-    let resolved = full_config
-        .context("No configuration file provided")?
-        .resolve()?;
+    if args.discover {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        let discovered = Config::discover(&cwd, ConfigFormat::Json)
+            .context("Failed to discover configuration")?;
+        full_config.compose(&discovered);
+    }
+
+    let full_config = full_config.context("No configuration file provided")?;
+
+    if args.merge {
+        println!("{}", full_config.to_json_string()?);
+        return Ok(());
+    }
+
+    let resolved = full_config.resolve()?;
     if args.debug {
         eprintln!("{:#?}", resolved);
     }
@@ -82,6 +113,47 @@ fn main() -> anyhow::Result<()> {
         eprintln!("Ignored rule errors: {:#?}", rule_errors);
     }
 
+    let kernel_abi = detected_abi();
+    let compat = resolved.compatibility_report(kernel_abi);
+    if args.report {
+        println!("Kernel ABI: {kernel_abi:?}");
+        for dropped in &compat.unsupported.dropped {
+            println!(
+                "unsupported: {} (requires {:?})",
+                dropped.description, dropped.minimum_abi
+            );
+        }
+        println!("unused filesystem access rights: {:?}", compat.unused_fs);
+        println!("unused network access rights: {:?}", compat.unused_net);
+        println!("unused scopes: {:?}", compat.unused_scoped);
+    }
+    if !args.no_unused_warning
+        && (!compat.unused_fs.is_empty()
+            || !compat.unused_net.is_empty()
+            || !compat.unused_scoped.is_empty())
+    {
+        eprintln!(
+            "warning: kernel ABI {kernel_abi:?} supports access rights this configuration never handles \
+             (fs: {:?}, net: {:?}, scoped: {:?}); consider tightening the policy",
+            compat.unused_fs, compat.unused_net, compat.unused_scoped
+        );
+    }
+
+    if args.check {
+        let check_report = resolved.check();
+        for issue in &check_report.path_issues {
+            println!("path error: {}: {}", issue.path.display(), issue.description);
+        }
+        for error in &rule_errors {
+            println!("rule error: {error}");
+        }
+        if check_report.is_empty() && rule_errors.is_empty() {
+            println!("OK: configuration is valid.");
+            return Ok(());
+        }
+        std::process::exit(1);
+    }
+
     let status = ruleset.restrict_self()?;
     if status.ruleset == RulesetStatus::NotEnforced {
         bail!("None of the restrictions can be enforced with the running kernel.");
@@ -1,11 +1,14 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use landlock::Errno;
-use landlockconfig::{Config, ConfigFormat};
+use landlock::{Errno, RulesetCreatedAttr, RulesetStatus, ABI};
+use landlockconfig::{
+    detected_abi, BuildRulesetError, CompatLevel, Config, ConfigFormat, Diagnostic, Diagnostics,
+    Severity,
+};
 use libc::c_char;
-use std::ffi::{c_int, CStr};
+use std::ffi::{c_int, CStr, CString};
 use std::fs::File;
-use std::io::{Error, ErrorKind};
+use std::io::{Error, ErrorKind, Write};
 use std::os::unix::io::{BorrowedFd, IntoRawFd, OwnedFd, RawFd};
 
 fn unwrap_errno<T>(err: T) -> c_int
@@ -47,18 +50,17 @@ where
     Ok(Box::into_raw(Box::new(parser(file)?)))
 }
 
-// TODO: Pass a set of buffers for warnings and errors.
-
 // TODO: Return NULL if the ruleset is not supported.
 
-// TODO: Add a flag to accept unknown JSON entries (e.g. for OCI specification).
-
 /// Parses a JSON configuration file
 ///
 /// # Parameters
 ///
 /// * `config_fd`: A file descriptor referring to a JSON configuration file.
 /// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a diagnostic entry is pushed into if parsing fails, on top of
+///   the returned `-errno`.
 ///
 /// # Return values
 ///
@@ -66,9 +68,16 @@ where
 ///   with landlockconfig_free().
 /// * -errno on error.
 #[no_mangle]
-pub extern "C" fn landlockconfig_parse_json_file(config_fd: RawFd, flags: u32) -> *mut Config {
-    parse_file(config_fd, flags, |file| {
-        Config::parse_json(file).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+pub extern "C" fn landlockconfig_parse_json_file(
+    config_fd: RawFd,
+    flags: u32,
+    diagnostics: *mut Diagnostics,
+) -> *mut Config {
+    let diagnostics = unsafe { diagnostics.as_mut() };
+    parse_file(config_fd, flags, |file| match diagnostics {
+        Some(diagnostics) => Config::parse_json_with_diagnostics(file, diagnostics)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+        None => Config::parse_json(file).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
     })
     .unwrap_or_else(|e| unwrap_errno(e) as *mut Config)
 }
@@ -79,6 +88,9 @@ pub extern "C" fn landlockconfig_parse_json_file(config_fd: RawFd, flags: u32) -
 ///
 /// * `config_fd`: A file descriptor referring to a TOML configuration file.
 /// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a diagnostic entry is pushed into if parsing fails, on top of
+///   the returned `-errno`.
 ///
 /// # Return values
 ///
@@ -86,11 +98,20 @@ pub extern "C" fn landlockconfig_parse_json_file(config_fd: RawFd, flags: u32) -
 ///   with landlockconfig_free().
 /// * -errno on error.
 #[no_mangle]
-pub extern "C" fn landlockconfig_parse_toml_file(config_fd: RawFd, flags: u32) -> *mut Config {
+pub extern "C" fn landlockconfig_parse_toml_file(
+    config_fd: RawFd,
+    flags: u32,
+    diagnostics: *mut Diagnostics,
+) -> *mut Config {
+    let diagnostics = unsafe { diagnostics.as_mut() };
     parse_file(config_fd, flags, |mut file| {
         let mut buffer = String::new();
         std::io::Read::read_to_string(&mut file, &mut buffer)?;
-        Config::parse_toml(&buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        match diagnostics {
+            Some(diagnostics) => Config::parse_toml_with_diagnostics(&buffer, diagnostics)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+            None => Config::parse_toml(&buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+        }
     })
     .unwrap_or_else(|e| unwrap_errno(e) as *mut Config)
 }
@@ -131,6 +152,9 @@ where
 /// * `buffer_ptr`: Pointer to the buffer containing JSON data.
 /// * `buffer_size`: Size of the buffer in bytes, or 0 if `buffer_ptr` is null-terminated.
 /// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a diagnostic entry is pushed into if parsing fails, on top of
+///   the returned `-errno`.
 ///
 /// # Return values
 ///
@@ -142,10 +166,16 @@ pub extern "C" fn landlockconfig_parse_json_buffer(
     buffer_ptr: *const u8,
     buffer_size: usize,
     flags: u32,
+    diagnostics: *mut Diagnostics,
 ) -> *mut Config {
-    parse_buffer(buffer_ptr, buffer_size, flags, |buffer| {
-        Config::parse_json(std::io::Cursor::new(buffer))
-            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    let diagnostics = unsafe { diagnostics.as_mut() };
+    parse_buffer(buffer_ptr, buffer_size, flags, |buffer| match diagnostics {
+        Some(diagnostics) => {
+            Config::parse_json_with_diagnostics(std::io::Cursor::new(buffer), diagnostics)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        }
+        None => Config::parse_json(std::io::Cursor::new(buffer))
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
     })
     .unwrap_or_else(|e| unwrap_errno(e) as *mut Config)
 }
@@ -157,6 +187,9 @@ pub extern "C" fn landlockconfig_parse_json_buffer(
 /// * `buffer_ptr`: Pointer to the buffer containing TOML data.
 /// * `buffer_size`: Size of the buffer in bytes, or 0 if `buffer_ptr` is null-terminated.
 /// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a diagnostic entry is pushed into if parsing fails, on top of
+///   the returned `-errno`.
 ///
 /// # Return values
 ///
@@ -168,11 +201,82 @@ pub extern "C" fn landlockconfig_parse_toml_buffer(
     buffer_ptr: *const u8,
     buffer_size: usize,
     flags: u32,
+    diagnostics: *mut Diagnostics,
 ) -> *mut Config {
+    let diagnostics = unsafe { diagnostics.as_mut() };
     parse_buffer(buffer_ptr, buffer_size, flags, |buffer| {
         let data =
             std::str::from_utf8(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
-        Config::parse_toml(data).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        match diagnostics {
+            Some(diagnostics) => Config::parse_toml_with_diagnostics(data, diagnostics)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+            None => Config::parse_toml(data).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+        }
+    })
+    .unwrap_or_else(|e| unwrap_errno(e) as *mut Config)
+}
+
+/// Parses an OCI runtime-spec `config.json` file, ingesting its `mounts`
+/// array as Landlock path rules
+///
+/// # Parameters
+///
+/// * `config_fd`: A file descriptor referring to an OCI runtime-spec
+///   `config.json` file.
+/// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a diagnostic entry is pushed into if parsing fails, on top of
+///   the returned `-errno`.
+///
+/// # Return values
+///
+/// * Pointer to a landlockconfig object on success. This object must be freed
+///   with landlockconfig_free().
+/// * -errno on error.
+#[no_mangle]
+pub extern "C" fn landlockconfig_parse_oci_file(
+    config_fd: RawFd,
+    flags: u32,
+    diagnostics: *mut Diagnostics,
+) -> *mut Config {
+    let diagnostics = unsafe { diagnostics.as_mut() };
+    parse_file(config_fd, flags, |file| match diagnostics {
+        Some(diagnostics) => Config::parse_oci_with_diagnostics(file, diagnostics)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+        None => Config::parse_oci(file).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+    })
+    .unwrap_or_else(|e| unwrap_errno(e) as *mut Config)
+}
+
+/// Parses an OCI runtime-spec `config.json` from a memory buffer, ingesting
+/// its `mounts` array as Landlock path rules
+///
+/// # Parameters
+///
+/// * `buffer_ptr`: Pointer to the buffer containing the OCI `config.json` data.
+/// * `buffer_size`: Size of the buffer in bytes, or 0 if `buffer_ptr` is null-terminated.
+/// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a diagnostic entry is pushed into if parsing fails, on top of
+///   the returned `-errno`.
+///
+/// # Return values
+///
+/// * Pointer to a landlockconfig object on success. This object must be freed
+///   with landlockconfig_free().
+/// * -errno on error.
+#[no_mangle]
+pub extern "C" fn landlockconfig_parse_oci_buffer(
+    buffer_ptr: *const u8,
+    buffer_size: usize,
+    flags: u32,
+    diagnostics: *mut Diagnostics,
+) -> *mut Config {
+    let diagnostics = unsafe { diagnostics.as_mut() };
+    parse_buffer(buffer_ptr, buffer_size, flags, |buffer| match diagnostics {
+        Some(diagnostics) => Config::parse_oci_with_diagnostics(buffer, diagnostics)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+        None => Config::parse_oci(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
     })
     .unwrap_or_else(|e| unwrap_errno(e) as *mut Config)
 }
@@ -181,6 +285,7 @@ fn parse_directory(
     dir_path: *const c_char,
     flags: u32,
     format: ConfigFormat,
+    diagnostics: Option<&mut Diagnostics>,
 ) -> Result<*mut Config, Errno> {
     if flags != 0 {
         return Err(Errno::new(libc::EINVAL));
@@ -191,8 +296,13 @@ fn parse_directory(
     }
 
     let path = unsafe { CStr::from_ptr(dir_path) }.to_str()?;
-    let config =
-        Config::parse_directory(path, format).map_err(|e| io_error_to_errno(Error::from(e)))?;
+    let config = match diagnostics {
+        Some(diagnostics) => Config::parse_directory_with_diagnostics(path, format, diagnostics)
+            .map_err(|e| io_error_to_errno(Error::from(e)))?,
+        None => {
+            Config::parse_directory(path, format).map_err(|e| io_error_to_errno(Error::from(e)))?
+        }
+    };
     Ok(Box::into_raw(Box::new(config)))
 }
 
@@ -202,6 +312,9 @@ fn parse_directory(
 ///
 /// * `dir_path`: A pointer to a null-terminated string containing the directory path.
 /// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a diagnostic entry is pushed into for every file that failed
+///   to parse, on top of the returned `-errno`.
 ///
 /// # Return values
 ///
@@ -212,8 +325,10 @@ fn parse_directory(
 pub extern "C" fn landlockconfig_parse_json_directory(
     dir_path: *const c_char,
     flags: u32,
+    diagnostics: *mut Diagnostics,
 ) -> *mut Config {
-    parse_directory(dir_path, flags, ConfigFormat::Json)
+    let diagnostics = unsafe { diagnostics.as_mut() };
+    parse_directory(dir_path, flags, ConfigFormat::Json, diagnostics)
         .unwrap_or_else(|e| unwrap_errno(e) as *mut Config)
 }
 
@@ -223,6 +338,9 @@ pub extern "C" fn landlockconfig_parse_json_directory(
 ///
 /// * `dir_path`: A pointer to a null-terminated string containing the directory path.
 /// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a diagnostic entry is pushed into for every file that failed
+///   to parse, on top of the returned `-errno`.
 ///
 /// # Return values
 ///
@@ -233,8 +351,10 @@ pub extern "C" fn landlockconfig_parse_json_directory(
 pub extern "C" fn landlockconfig_parse_toml_directory(
     dir_path: *const c_char,
     flags: u32,
+    diagnostics: *mut Diagnostics,
 ) -> *mut Config {
-    parse_directory(dir_path, flags, ConfigFormat::Toml)
+    let diagnostics = unsafe { diagnostics.as_mut() };
+    parse_directory(dir_path, flags, ConfigFormat::Toml, diagnostics)
         .unwrap_or_else(|e| unwrap_errno(e) as *mut Config)
 }
 
@@ -250,6 +370,39 @@ pub unsafe extern "C" fn landlockconfig_free(config: *mut Config) {
     }
 }
 
+fn abi_to_version(abi: ABI) -> c_int {
+    match abi {
+        ABI::V1 => 1,
+        ABI::V2 => 2,
+        ABI::V3 => 3,
+        ABI::V4 => 4,
+        ABI::V5 => 5,
+        ABI::V6 => 6,
+        // The `landlock` crate's `ABI` is non_exhaustive: new variants
+        // should be treated as "at least as capable as the latest we know".
+        _ => 6,
+    }
+}
+
+/// Returns the best Landlock ABI version supported by the running kernel.
+///
+/// # Parameters
+///
+/// * `flags`: Must be 0.
+///
+/// # Return values
+///
+/// * The ABI version (a positive integer) on success.
+/// * -errno on error.
+#[no_mangle]
+pub extern "C" fn landlockconfig_detect_abi(flags: u32) -> c_int {
+    if flags != 0 {
+        return unwrap_errno(Errno::new(libc::EINVAL));
+    }
+
+    abi_to_version(detected_abi())
+}
+
 // TODO: Also return RestrictionStatus
 
 /// Creates a ruleset from a landlockconfig object
@@ -258,6 +411,11 @@ pub unsafe extern "C" fn landlockconfig_free(config: *mut Config) {
 ///
 /// * `config`: A pointer to a landlockconfig object.
 /// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null). Every rule that had to be ignored (e.g. a `pathBeneath` parent
+///   that could not be opened) is pushed as a warning entry, and a hard
+///   failure is also pushed as an error entry, on top of the returned
+///   `-errno`.
 ///
 /// # Safety
 ///
@@ -269,7 +427,11 @@ pub unsafe extern "C" fn landlockconfig_free(config: *mut Config) {
 /// * The ruleset file descriptor on success.
 /// * -errno on error.
 #[no_mangle]
-pub unsafe extern "C" fn landlockconfig_build_ruleset(config: *const Config, flags: u32) -> RawFd {
+pub unsafe extern "C" fn landlockconfig_build_ruleset(
+    config: *const Config,
+    flags: u32,
+    diagnostics: *mut Diagnostics,
+) -> RawFd {
     if flags != 0 {
         return unwrap_errno(Errno::new(libc::EINVAL));
     }
@@ -278,30 +440,487 @@ pub unsafe extern "C" fn landlockconfig_build_ruleset(config: *const Config, fla
         return unwrap_errno(Errno::new(libc::EFAULT));
     }
 
+    let diagnostics = unsafe { diagnostics.as_mut() };
+
     // TODO: Avoid cloning the config.
     let resolved = match unsafe { &*config }.clone().resolve() {
         Ok(resolved) => resolved,
         Err(e) => return unwrap_errno(e),
     };
-    resolved
-        .build_ruleset()
-        .map(|(r, _)| {
+    let built = match diagnostics {
+        Some(diagnostics) => resolved.build_ruleset_with_diagnostics(diagnostics),
+        None => resolved.build_ruleset().map(|(ruleset, _)| ruleset),
+    };
+    built
+        .map(|r| {
             let fd: Option<OwnedFd> = r.into();
             fd.map(|fd| fd.into_raw_fd()).unwrap_or(-1)
         })
         .unwrap_or_else(unwrap_errno)
 }
 
+/// Runs semantic validation (see `Config::validate`) against a parsed
+/// landlockconfig object, pushing a warning entry into `diagnostics` for
+/// every issue found instead of collapsing them down to a single errno.
+/// Pass a nonzero `check_paths` to additionally check that every
+/// `pathBeneath` parent exists and can be opened, via
+/// `Config::validate_with_paths`.
+///
+/// # Parameters
+///
+/// * `config`: A pointer to a landlockconfig object.
+/// * `check_paths`: Nonzero to also validate that `pathBeneath` parents
+///   exist and can be opened.
+/// * `flags`: Must be 0.
+/// * `diagnostics`: An optional `landlockconfig_diagnostics` buffer (may be
+///   null) that a warning entry is pushed into for every issue found, e.g. a
+///   rule allowing an access right the ruleset never handles, or an empty
+///   ruleset.
+///
+/// # Safety
+///
+/// `config` must have been returned by a `landlockconfig_parse_*` function.
+///
+/// # Return values
+///
+/// * 0 if no issue was found.
+/// * -errno if at least one issue was found (`EINVAL`), or validation
+///   itself failed (e.g. a `pathBeneath` parent could not be resolved).
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_validate(
+    config: *const Config,
+    check_paths: c_int,
+    flags: u32,
+    diagnostics: *mut Diagnostics,
+) -> c_int {
+    if flags != 0 {
+        return unwrap_errno(Errno::new(libc::EINVAL));
+    }
+
+    if config.is_null() {
+        return unwrap_errno(Errno::new(libc::EFAULT));
+    }
+
+    let config = unsafe { &*config };
+    let errors = if check_paths != 0 {
+        match config.validate_with_paths() {
+            Ok(errors) => errors,
+            Err(e) => return unwrap_errno(e),
+        }
+    } else {
+        config.validate()
+    };
+
+    if let Some(diagnostics) = unsafe { diagnostics.as_mut() } {
+        for error in &errors {
+            diagnostics.push(Diagnostic::from(error));
+        }
+    }
+
+    if errors.is_empty() {
+        0
+    } else {
+        unwrap_errno(Errno::new(libc::EINVAL))
+    }
+}
+
+/// C ABI mirror of `landlockconfig::CompatLevel`, for
+/// [`landlockconfig_build_ruleset_compat`].
+#[repr(C)]
+pub enum LandlockconfigCompatLevel {
+    HardRequirement = 0,
+    SoftRequirement = 1,
+    BestEffort = 2,
+}
+
+impl From<LandlockconfigCompatLevel> for CompatLevel {
+    fn from(level: LandlockconfigCompatLevel) -> Self {
+        match level {
+            LandlockconfigCompatLevel::HardRequirement => CompatLevel::HardRequirement,
+            LandlockconfigCompatLevel::SoftRequirement => CompatLevel::SoftRequirement,
+            LandlockconfigCompatLevel::BestEffort => CompatLevel::BestEffort,
+        }
+    }
+}
+
+/// Like [`landlockconfig_build_ruleset`], but negotiates the handled access
+/// sets against the running kernel's detected Landlock ABI instead of
+/// assuming every handled access in `config` is supported, per `level`.
+///
+/// # Parameters
+///
+/// * `config`: A pointer to a landlockconfig object.
+/// * `level`: How strictly to treat access rights the running kernel can't
+///   enforce.
+/// * `flags`: Must be 0.
+/// * `dropped_count`: An optional out-parameter (may be null) set to the
+///   number of access rights and rules that had to be dropped to fit the
+///   running kernel's ABI (always 0 in `HardRequirement` mode, since that
+///   mode errors instead of dropping anything).
+///
+/// # Safety
+///
+/// `config` must have been returned by a `landlockconfig_parse_*` function.
+///
+/// # Return values
+///
+/// * The ruleset file descriptor on success.
+/// * -errno on error (`ENOTSUP` if the running kernel can't satisfy
+///   `level`).
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_build_ruleset_compat(
+    config: *const Config,
+    level: LandlockconfigCompatLevel,
+    flags: u32,
+    dropped_count: *mut usize,
+) -> RawFd {
+    if flags != 0 {
+        return unwrap_errno(Errno::new(libc::EINVAL));
+    }
+
+    if config.is_null() {
+        return unwrap_errno(Errno::new(libc::EFAULT));
+    }
+
+    unsafe { &*config }
+        .build_ruleset_with_compat(level.into())
+        .map(|(ruleset, _rule_errors, report)| {
+            if let Some(out) = unsafe { dropped_count.as_mut() } {
+                *out = report.dropped.len();
+            }
+            let fd: Option<OwnedFd> = ruleset.into();
+            fd.map(|fd| fd.into_raw_fd()).unwrap_or(-1)
+        })
+        .unwrap_or_else(unwrap_errno)
+}
+
+/// Out-parameter of [`landlockconfig_restrict_self`], mirroring
+/// `landlock::RestrictionStatus` plus the detected Landlock ABI so a
+/// caller that only gets a success/failure `c_int` back can still log how
+/// the restriction actually landed.
+#[repr(C)]
+pub struct LandlockconfigRestrictionStatus {
+    /// 0: not enforced, 1: partially enforced, 2: fully enforced.
+    pub ruleset_status: c_int,
+    pub enforced_abi: u32,
+}
+
+/// Builds a ruleset from a landlockconfig object and applies it to the
+/// current process, like calling `set_no_new_privs` then `restrict_self`
+/// directly with the `landlock` crate, so a non-Rust caller can sandbox
+/// itself in one call instead of juggling the ruleset fd.
+///
+/// # Parameters
+///
+/// * `config`: A pointer to a landlockconfig object.
+/// * `flags`: Must be 0.
+/// * `status`: An optional out-parameter (may be null), set on success.
+///
+/// # Safety
+///
+/// `config` must have been returned by a `landlockconfig_parse_*`
+/// function. `status`, if non-null, must point to writable memory for a
+/// [`LandlockconfigRestrictionStatus`].
+///
+/// # Return values
+///
+/// * 0 on success, with `*status` set if `status` is non-null.
+/// * -errno on error.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_restrict_self(
+    config: *const Config,
+    flags: u32,
+    status: *mut LandlockconfigRestrictionStatus,
+) -> c_int {
+    if flags != 0 {
+        return unwrap_errno(Errno::new(libc::EINVAL));
+    }
+
+    if config.is_null() {
+        return unwrap_errno(Errno::new(libc::EFAULT));
+    }
+
+    // TODO: Avoid cloning the config.
+    let resolved = match unsafe { &*config }.clone().resolve() {
+        Ok(resolved) => resolved,
+        Err(e) => return unwrap_errno(e),
+    };
+
+    let restriction = match resolved.build_ruleset() {
+        Ok((mut ruleset, _rule_errors)) => ruleset
+            .set_no_new_privs(true)
+            .restrict_self()
+            .map_err(BuildRulesetError::from),
+        Err(e) => Err(e),
+    };
+
+    match restriction {
+        Ok(restriction) => {
+            if let Some(out) = unsafe { status.as_mut() } {
+                out.ruleset_status = match restriction.ruleset {
+                    RulesetStatus::FullyEnforced => 2,
+                    RulesetStatus::PartiallyEnforced => 1,
+                    RulesetStatus::NotEnforced => 0,
+                };
+                out.enforced_abi = detected_abi() as u32;
+            }
+            0
+        }
+        Err(e) => unwrap_errno(e),
+    }
+}
+
+/// Writes `config` to `config_fd` via `writer`, failing with the error
+/// code `writer` maps to through [`Errno`] instead of a parse error.
+fn write_config<F>(config: &Config, config_fd: RawFd, writer: F) -> Result<(), Errno>
+where
+    F: FnOnce(&Config, File) -> Result<(), Errno>,
+{
+    let fd = unsafe { BorrowedFd::borrow_raw(config_fd) };
+    // Checks if it is a valid file descriptor.
+    let file = File::from(fd.try_clone_to_owned()?);
+    writer(config, file)
+}
+
+/// Serializes a landlockconfig object to `config_fd` as JSON, in the same
+/// canonical, de-duplicated form as `Config::to_json_string`.
+///
+/// # Parameters
+///
+/// * `config`: A pointer to a landlockconfig object.
+/// * `config_fd`: A file descriptor to write the JSON document to.
+/// * `flags`: Must be 0.
+///
+/// # Safety
+///
+/// `config` must have been returned by a `landlockconfig_parse_*` function.
+///
+/// # Return values
+///
+/// * 0 on success.
+/// * -errno on error.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_write_json(
+    config: *const Config,
+    config_fd: RawFd,
+    flags: u32,
+) -> c_int {
+    if flags != 0 {
+        return unwrap_errno(Errno::new(libc::EINVAL));
+    }
+
+    if config.is_null() {
+        return unwrap_errno(Errno::new(libc::EFAULT));
+    }
+
+    write_config(unsafe { &*config }, config_fd, |config, file| {
+        config
+            .to_json_writer(file)
+            .map_err(|_| Errno::new(libc::EIO))
+    })
+    .map(|()| 0)
+    .unwrap_or_else(unwrap_errno)
+}
+
+/// TOML counterpart of [`landlockconfig_write_json`].
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_write_toml(
+    config: *const Config,
+    config_fd: RawFd,
+    flags: u32,
+) -> c_int {
+    if flags != 0 {
+        return unwrap_errno(Errno::new(libc::EINVAL));
+    }
+
+    if config.is_null() {
+        return unwrap_errno(Errno::new(libc::EFAULT));
+    }
+
+    write_config(unsafe { &*config }, config_fd, |config, mut file| {
+        let toml = config.to_toml_string().map_err(|_| Errno::new(libc::EIO))?;
+        file.write_all(toml.as_bytes())
+            .map_err(|_| Errno::new(libc::EIO))
+    })
+    .map(|()| 0)
+    .unwrap_or_else(unwrap_errno)
+}
+
+/// Creates an empty diagnostics buffer to be passed into
+/// `landlockconfig_parse_*` and `landlockconfig_build_ruleset`.
+///
+/// # Returns
+///
+/// * Pointer to a landlockconfig_diagnostics object on success. This object
+///   must be freed with landlockconfig_diagnostics_free().
+#[no_mangle]
+pub extern "C" fn landlockconfig_diagnostics_new() -> *mut Diagnostics {
+    Box::into_raw(Box::new(Diagnostics::new()))
+}
+
+/// Frees a landlockconfig_diagnostics object
+///
+/// # Safety
+///
+/// The pointer must have been returned by landlockconfig_diagnostics_new(),
+/// or be null.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_diagnostics_free(diagnostics: *mut Diagnostics) {
+    if !diagnostics.is_null() {
+        drop(unsafe { Box::from_raw(diagnostics) });
+    }
+}
+
+/// Returns the number of entries accumulated in `diagnostics`.
+///
+/// # Safety
+///
+/// `diagnostics` must have been returned by landlockconfig_diagnostics_new()
+/// and not yet freed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_diagnostics_len(diagnostics: *const Diagnostics) -> usize {
+    match unsafe { diagnostics.as_ref() } {
+        Some(diagnostics) => diagnostics.len(),
+        None => 0,
+    }
+}
+
+/// A single entry read out of a `landlockconfig_diagnostics` buffer, with
+/// its strings rendered as owned, NUL-terminated C strings.
+pub struct DiagnosticEntry {
+    severity: c_int,
+    code: CString,
+    subject: Option<CString>,
+    message: CString,
+}
+
+/// Embedded NUL bytes cannot occur in a C string: messages are built from
+/// `serde`/`landlock` error text and path/port renditions, none of which
+/// contain NUL.
+fn to_cstring(s: &str) -> CString {
+    CString::new(s).unwrap_or_default()
+}
+
+impl From<&Diagnostic> for DiagnosticEntry {
+    fn from(diagnostic: &Diagnostic) -> Self {
+        Self {
+            severity: match diagnostic.severity {
+                Severity::Warning => 0,
+                Severity::Error => 1,
+            },
+            code: to_cstring(diagnostic.code),
+            subject: diagnostic.subject.as_deref().map(to_cstring),
+            message: to_cstring(&diagnostic.message),
+        }
+    }
+}
+
+/// Returns the entry at `index` in `diagnostics`.
+///
+/// # Safety
+///
+/// `diagnostics` must have been returned by landlockconfig_diagnostics_new()
+/// and not yet freed, or be null.
+///
+/// # Returns
+///
+/// * Pointer to a landlockconfig_diagnostic_entry object on success. This
+///   object must be freed with landlockconfig_diagnostic_entry_free().
+/// * Null if `index` is out of range.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_diagnostics_get(
+    diagnostics: *const Diagnostics,
+    index: usize,
+) -> *mut DiagnosticEntry {
+    let entry =
+        unsafe { diagnostics.as_ref() }.and_then(|diagnostics| diagnostics.iter().nth(index));
+    match entry {
+        Some(diagnostic) => Box::into_raw(Box::new(DiagnosticEntry::from(diagnostic))),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a landlockconfig_diagnostic_entry object
+///
+/// # Safety
+///
+/// The pointer must have been returned by landlockconfig_diagnostics_get(),
+/// or be null.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_diagnostic_entry_free(entry: *mut DiagnosticEntry) {
+    if !entry.is_null() {
+        drop(unsafe { Box::from_raw(entry) });
+    }
+}
+
+/// Returns the severity of `entry`: 0 for a warning, 1 for an error.
+///
+/// # Safety
+///
+/// `entry` must have been returned by landlockconfig_diagnostics_get() and
+/// not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_diagnostic_entry_severity(
+    entry: *const DiagnosticEntry,
+) -> c_int {
+    unsafe { &*entry }.severity
+}
+
+/// Returns the stable, machine-readable code of `entry` (e.g. `"rule_error"`),
+/// valid as long as `entry` is not freed.
+///
+/// # Safety
+///
+/// `entry` must have been returned by landlockconfig_diagnostics_get() and
+/// not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_diagnostic_entry_code(
+    entry: *const DiagnosticEntry,
+) -> *const c_char {
+    unsafe { &*entry }.code.as_ptr()
+}
+
+/// Returns the offending path/port/access-right of `entry`, or null if not
+/// applicable, valid as long as `entry` is not freed.
+///
+/// # Safety
+///
+/// `entry` must have been returned by landlockconfig_diagnostics_get() and
+/// not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_diagnostic_entry_subject(
+    entry: *const DiagnosticEntry,
+) -> *const c_char {
+    unsafe { &*entry }
+        .subject
+        .as_ref()
+        .map(|s| s.as_ptr())
+        .unwrap_or(std::ptr::null())
+}
+
+/// Returns the human-readable message of `entry`, valid as long as `entry`
+/// is not freed.
+///
+/// # Safety
+///
+/// `entry` must have been returned by landlockconfig_diagnostics_get() and
+/// not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn landlockconfig_diagnostic_entry_message(
+    entry: *const DiagnosticEntry,
+) -> *const c_char {
+    unsafe { &*entry }.message.as_ptr()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::ffi::CString;
 
     #[test]
     fn test_parse_directory_enotdir() {
         let file_path =
             CString::new(std::env::current_exe().unwrap().as_path().to_str().unwrap()).unwrap();
-        let result = parse_directory(file_path.as_ptr(), 0, ConfigFormat::Json);
+        let result = parse_directory(file_path.as_ptr(), 0, ConfigFormat::Json, None);
 
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -310,7 +929,7 @@ mod tests {
 
     #[test]
     fn test_parse_directory_null_path() {
-        let result = parse_directory(std::ptr::null(), 0, ConfigFormat::Json);
+        let result = parse_directory(std::ptr::null(), 0, ConfigFormat::Json, None);
 
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -321,7 +940,7 @@ mod tests {
     fn test_parse_directory_invalid_flags() {
         let file_path =
             CString::new(std::env::current_exe().unwrap().as_path().to_str().unwrap()).unwrap();
-        let result = parse_directory(file_path.as_ptr(), 1, ConfigFormat::Json);
+        let result = parse_directory(file_path.as_ptr(), 1, ConfigFormat::Json, None);
 
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -332,7 +951,7 @@ mod tests {
     fn test_parse_directory_nonexistent() {
         let nonexistent_path = CString::new("/nonexistent/directory/").unwrap();
 
-        let result = parse_directory(nonexistent_path.as_ptr(), 0, ConfigFormat::Json);
+        let result = parse_directory(nonexistent_path.as_ptr(), 0, ConfigFormat::Json, None);
 
         assert!(result.is_err());
         let err = result.unwrap_err();
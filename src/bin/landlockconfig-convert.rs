@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! A standalone CLI that re-emits a landlockconfig JSON/TOML file in the
+//! other format (or the same one, canonicalized), mirroring the
+//! `toml2json`/`json2toml` conversion examples shipped with the `toml`
+//! crate. Lets packagers and config-migration tooling round-trip a policy
+//! file without writing any Rust.
+
+use anyhow::Context;
+use clap::{Parser, ValueEnum};
+use landlockconfig::Config;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Format {
+    Json,
+    Toml,
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Convert a landlockconfig file between JSON and TOML")]
+struct Args {
+    /// The config file to read. JSON or TOML is detected from the
+    /// `.json`/`.toml` extension, falling back to content sniffing.
+    input: PathBuf,
+    /// Format to emit. Defaults to the opposite of the detected input
+    /// format, so `--output toml a.json` and `a.json` alone both convert.
+    #[arg(short, long)]
+    output: Option<Format>,
+}
+
+fn detect_format(path: &Path, data: &str) -> Format {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => Format::Json,
+        Some("toml") => Format::Toml,
+        _ => {
+            if serde_json::from_str::<serde_json::Value>(data).is_ok() {
+                Format::Json
+            } else {
+                Format::Toml
+            }
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let data = fs::read_to_string(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let input_format = detect_format(&args.input, &data);
+
+    let config = match input_format {
+        Format::Json => Config::parse_json(data.as_bytes())
+            .with_context(|| format!("{}: invalid JSON config", args.input.display()))?,
+        Format::Toml => Config::parse_toml(&data)
+            .with_context(|| format!("{}: invalid TOML config", args.input.display()))?,
+    };
+
+    let output_format = args.output.unwrap_or(match input_format {
+        Format::Json => Format::Toml,
+        Format::Toml => Format::Json,
+    });
+
+    let rendered = match output_format {
+        Format::Json => config.to_json_string()?,
+        Format::Toml => config.to_toml_string()?,
+    };
+    print!("{rendered}");
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! A standalone CLI that validates landlockconfig JSON/TOML files against
+//! the embedded JSON Schema and the real [`Config`] parser, mirroring the
+//! `--instance`-per-file, non-zero-exit-on-failure pattern of jsonschema-rs's
+//! own `jsonschema` CLI. Lets CI pipelines and packagers lint Landlock
+//! policy files without writing any Rust.
+
+use anyhow::Context;
+use clap::Parser;
+use landlockconfig::{validate_config, Config};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(about = "Validate landlockconfig JSON/TOML files")]
+struct Args {
+    /// A config file to validate. JSON or TOML is detected from the
+    /// `.json`/`.toml` extension, falling back to content sniffing.
+    #[arg(long = "instance", required = true)]
+    instances: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Json,
+    Toml,
+}
+
+fn detect_format(path: &Path, data: &str) -> Format {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => Format::Json,
+        Some("toml") => Format::Toml,
+        _ => {
+            if serde_json::from_str::<serde_json::Value>(data).is_ok() {
+                Format::Json
+            } else {
+                Format::Toml
+            }
+        }
+    }
+}
+
+/// Validates a single config file against both the embedded schema and the
+/// real parser, printing a pass/fail report. Returns `false` if either check
+/// failed.
+fn validate_instance(path: &Path) -> anyhow::Result<bool> {
+    let data =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let (schema_result, parse_result) = match detect_format(path, &data) {
+        Format::Json => {
+            let value: serde_json::Value = serde_json::from_str(&data)
+                .with_context(|| format!("{}: not valid JSON", path.display()))?;
+            (
+                validate_config(&value),
+                Config::parse_json(data.as_bytes()).map(|_| ()).map_err(|e| e.to_string()),
+            )
+        }
+        Format::Toml => {
+            let value: toml::Value = toml::from_str(&data)
+                .with_context(|| format!("{}: not valid TOML", path.display()))?;
+            let json = serde_json::to_value(value).with_context(|| {
+                format!("{}: TOML value is not representable as JSON", path.display())
+            })?;
+            (
+                validate_config(&json),
+                Config::parse_toml(&data).map(|_| ()).map_err(|e| e.to_string()),
+            )
+        }
+    };
+
+    if schema_result.is_ok() && parse_result.is_ok() {
+        println!("{}: ok", path.display());
+        return Ok(true);
+    }
+
+    println!("{}: FAIL", path.display());
+    if let Err(errors) = &schema_result {
+        for error in errors {
+            println!("  schema: {error}");
+        }
+    }
+    if let Err(message) = &parse_result {
+        println!("  parser: {message}");
+    }
+    Ok(false)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut all_valid = true;
+    for instance in &args.instances {
+        if !validate_instance(instance)? {
+            all_valid = false;
+        }
+    }
+
+    if !all_valid {
+        std::process::exit(1);
+    }
+    Ok(())
+}
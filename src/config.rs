@@ -1,13 +1,22 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+use crate::diagnostic::{
+    line_col_from_offset, Diagnostic, DiagnosticKind, Diagnostics, ParseDiagnostic, Severity,
+};
 use crate::nonempty::NonEmptyStruct;
-use crate::parser::{JsonConfig, TemplateString, TomlConfig};
-use crate::variable::{NameError, ResolveError, Variables, VecStringIterator};
+use crate::parser::{
+    access_fs_items, access_net_items, fold_access_fs_items, fold_access_net_items,
+    fold_scope_items, minimum_abi_fs, minimum_abi_net, minimum_abi_scope, scope_items,
+    JsonAbiRange, JsonCompatLevel, JsonConfig, JsonFsAccessEntry, JsonNetAccessEntry, JsonNetPort,
+    JsonPathBeneath, JsonRuleset, JsonScopeEntry, JsonVariable, PortRange, ProfileError,
+    TemplateString, TemplateToken, TomlConfig,
+};
+use crate::variable::{lexically_normalize, NameError, ResolveError, Variables, VecStringIterator};
 use landlock::{
     AccessFs, AccessNet, BitFlags, NetPort, PathBeneath, PathFd, PathFdError, Ruleset, RulesetAttr,
-    RulesetCreated, RulesetCreatedAttr, RulesetError, Scope,
+    RulesetCreated, RulesetCreatedAttr, RulesetError, Scope, ABI,
 };
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{self, File};
 use std::num::TryFromIntError;
 use std::path::{Path, PathBuf};
@@ -22,17 +31,43 @@ pub enum BuildRulesetError {
     Integer(#[from] TryFromIntError),
     #[error(transparent)]
     Ruleset(#[from] RulesetError),
+    #[error(transparent)]
+    Resolve(#[from] ResolveError),
+    /// Raised by [`Config::build_ruleset_with_compat`] in
+    /// [`CompatLevel::HardRequirement`] mode when the running kernel's
+    /// Landlock ABI can't enforce every handled access right, identifying
+    /// the first offending one.
+    #[error("the running kernel (ABI {detected:?}) doesn't support {description} (requires ABI {minimum_abi:?})")]
+    Unsupported {
+        description: String,
+        minimum_abi: ABI,
+        detected: ABI,
+    },
+}
+
+impl From<&BuildRulesetError> for Diagnostic {
+    fn from(err: &BuildRulesetError) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code: "build_ruleset_error",
+            subject: None,
+            message: err.to_string(),
+        }
+    }
 }
 
 #[cfg_attr(test, derive(Default))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Config {
+    pub(crate) abi: Option<AbiRequirement>,
+    pub(crate) compatibility: CompatLevel,
     pub(crate) variables: Variables,
     pub(crate) handled_fs: BitFlags<AccessFs>,
     pub(crate) handled_net: BitFlags<AccessNet>,
     pub(crate) scoped: BitFlags<Scope>,
     pub(crate) rules_path_beneath: BTreeMap<TemplateString, BitFlags<AccessFs>>,
-    pub(crate) rules_net_port: BTreeMap<u64, BitFlags<AccessNet>>,
+    pub(crate) rules_net_port: BTreeMap<PortRange, BitFlags<AccessNet>>,
+    pub(crate) provenance: Provenance,
 }
 
 #[cfg_attr(test, derive(Default))]
@@ -47,20 +82,491 @@ pub struct ResolvedConfig {
     // Thanks to PathBuf, paths are normalized.
     pub(crate) rules_path_beneath: BTreeMap<PathBuf, BitFlags<AccessFs>>,
     pub(crate) rules_net_port: BTreeMap<u64, BitFlags<AccessNet>>,
+    pub(crate) provenance: ResolvedProvenance,
+}
+
+/// Where a tracked [`Config`] entry's value came from, once provenance
+/// tracking has been opted into via [`Config::with_source`]. Modeled on
+/// jj's `ConfigSource`/`AnnotatedValue` and Mercurial's per-layer config
+/// origins: a file this crate parsed, or a caller-supplied label for a
+/// `Config` assembled some other way (e.g. built directly from
+/// `parse_json`/`parse_toml` rather than `parse_directory`).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Source {
+    File(PathBuf),
+    Label(String),
+}
+
+/// Per-entry source tracking for a [`Config`], populated only through
+/// [`Config::with_source`]. Every collection here stays empty unless a
+/// caller opts in, so parsing/composing a `Config` the normal way costs
+/// nothing extra, and `PartialEq`/tests that never call `with_source`
+/// never observe a difference.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Provenance {
+    handled_fs: Vec<(BitFlags<AccessFs>, Vec<Source>)>,
+    handled_net: Vec<(BitFlags<AccessNet>, Vec<Source>)>,
+    scoped: Vec<(BitFlags<Scope>, Vec<Source>)>,
+    rules_path_beneath: BTreeMap<TemplateString, Vec<Source>>,
+    rules_net_port: BTreeMap<PortRange, Vec<Source>>,
+}
+
+/// Like [`Provenance`], but keyed the way [`ResolvedConfig`] itself is:
+/// by concrete path/port rather than by template, once variables have
+/// been expanded. Built by [`Config::resolve`] from a [`Provenance`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ResolvedProvenance {
+    handled_fs: Vec<(BitFlags<AccessFs>, Vec<Source>)>,
+    handled_net: Vec<(BitFlags<AccessNet>, Vec<Source>)>,
+    scoped: Vec<(BitFlags<Scope>, Vec<Source>)>,
+    rules_path_beneath: BTreeMap<PathBuf, Vec<Source>>,
+    rules_net_port: BTreeMap<u64, Vec<Source>>,
+}
+
+fn add_source(sources: &mut Vec<Source>, source: &Source) {
+    if !sources.contains(source) {
+        sources.push(source.clone());
+        sources.sort();
+    }
+}
+
+fn origins_for_fs(entries: &[(BitFlags<AccessFs>, Vec<Source>)], flag: AccessFs) -> &[Source] {
+    entries
+        .iter()
+        .find(|(f, _)| f.contains(flag))
+        .map(|(_, sources)| sources.as_slice())
+        .unwrap_or(&[])
+}
+
+fn origins_for_net(entries: &[(BitFlags<AccessNet>, Vec<Source>)], flag: AccessNet) -> &[Source] {
+    entries
+        .iter()
+        .find(|(f, _)| f.contains(flag))
+        .map(|(_, sources)| sources.as_slice())
+        .unwrap_or(&[])
+}
+
+fn origins_for_scope(entries: &[(BitFlags<Scope>, Vec<Source>)], flag: Scope) -> &[Source] {
+    entries
+        .iter()
+        .find(|(f, _)| f.contains(flag))
+        .map(|(_, sources)| sources.as_slice())
+        .unwrap_or(&[])
+}
+
+impl Provenance {
+    fn record_fs(&mut self, access: BitFlags<AccessFs>, source: &Source) {
+        for flag in access.iter() {
+            let flag: BitFlags<AccessFs> = flag.into();
+            match self.handled_fs.iter_mut().find(|(f, _)| *f == flag) {
+                Some((_, sources)) => add_source(sources, source),
+                None => self.handled_fs.push((flag, vec![source.clone()])),
+            }
+        }
+    }
+
+    fn record_net(&mut self, access: BitFlags<AccessNet>, source: &Source) {
+        for flag in access.iter() {
+            let flag: BitFlags<AccessNet> = flag.into();
+            match self.handled_net.iter_mut().find(|(f, _)| *f == flag) {
+                Some((_, sources)) => add_source(sources, source),
+                None => self.handled_net.push((flag, vec![source.clone()])),
+            }
+        }
+    }
+
+    fn record_scope(&mut self, access: BitFlags<Scope>, source: &Source) {
+        for flag in access.iter() {
+            let flag: BitFlags<Scope> = flag.into();
+            match self.scoped.iter_mut().find(|(f, _)| *f == flag) {
+                Some((_, sources)) => add_source(sources, source),
+                None => self.scoped.push((flag, vec![source.clone()])),
+            }
+        }
+    }
+
+    fn record_path(&mut self, path: &TemplateString, source: &Source) {
+        add_source(
+            self.rules_path_beneath.entry(path.clone()).or_default(),
+            source,
+        );
+    }
+
+    fn record_port(&mut self, port: PortRange, source: &Source) {
+        add_source(self.rules_net_port.entry(port).or_default(), source);
+    }
+
+    /// Drops provenance for any flag no longer in `mask`, and unions in
+    /// `other`'s sources for every flag that remains: mirrors
+    /// [`Config::compose`]'s intersect-then-union treatment of the access
+    /// right itself.
+    fn compose_fs(
+        &mut self,
+        mask: BitFlags<AccessFs>,
+        other: &[(BitFlags<AccessFs>, Vec<Source>)],
+    ) {
+        self.handled_fs.retain(|(flag, _)| mask.contains(*flag));
+        for (flag, sources) in &mut self.handled_fs {
+            if let Some((_, other_sources)) = other.iter().find(|(f, _)| f == flag) {
+                for source in other_sources {
+                    add_source(sources, source);
+                }
+            }
+        }
+        for (flag, other_sources) in other {
+            if mask.contains(*flag) && !self.handled_fs.iter().any(|(f, _)| f == flag) {
+                self.handled_fs.push((*flag, other_sources.clone()));
+            }
+        }
+    }
+
+    fn compose_net(
+        &mut self,
+        mask: BitFlags<AccessNet>,
+        other: &[(BitFlags<AccessNet>, Vec<Source>)],
+    ) {
+        self.handled_net.retain(|(flag, _)| mask.contains(*flag));
+        for (flag, sources) in &mut self.handled_net {
+            if let Some((_, other_sources)) = other.iter().find(|(f, _)| f == flag) {
+                for source in other_sources {
+                    add_source(sources, source);
+                }
+            }
+        }
+        for (flag, other_sources) in other {
+            if mask.contains(*flag) && !self.handled_net.iter().any(|(f, _)| f == flag) {
+                self.handled_net.push((*flag, other_sources.clone()));
+            }
+        }
+    }
+
+    fn compose_scoped(&mut self, mask: BitFlags<Scope>, other: &[(BitFlags<Scope>, Vec<Source>)]) {
+        self.scoped.retain(|(flag, _)| mask.contains(*flag));
+        for (flag, sources) in &mut self.scoped {
+            if let Some((_, other_sources)) = other.iter().find(|(f, _)| f == flag) {
+                for source in other_sources {
+                    add_source(sources, source);
+                }
+            }
+        }
+        for (flag, other_sources) in other {
+            if mask.contains(*flag) && !self.scoped.iter().any(|(f, _)| f == flag) {
+                self.scoped.push((*flag, other_sources.clone()));
+            }
+        }
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error(transparent)]
     Name(#[from] NameError),
+    #[error(transparent)]
+    Profile(#[from] ProfileError),
+    #[error(transparent)]
+    Resolve(#[from] ResolveError),
+}
+
+/// A semantic issue found by [`Config::validate`]/[`Config::validate_with_paths`].
+///
+/// Unlike [`ConfigError`]/[`ParseJsonError`], these don't prevent a
+/// `Config` from being built: the document was structurally valid, but
+/// something in it is nonsensical or ineffective, e.g. a rule that allows
+/// an access right the ruleset never handles.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// A `pathBeneath` rule allows an access right that is not present in
+    /// `handledAccessFs`, so Landlock will never actually grant it.
+    #[error("pathBeneath rule for `{path}` allows `{access:?}`, which is not in handledAccessFs")]
+    UnhandledPathAccess {
+        path: String,
+        access: BitFlags<AccessFs>,
+    },
+    /// A `netPort` rule allows an access right that is not present in
+    /// `handledAccessNet`, so Landlock will never actually grant it.
+    #[error("netPort rule for `{port}` allows `{access:?}`, which is not in handledAccessNet")]
+    UnhandledNetAccess {
+        port: String,
+        access: BitFlags<AccessNet>,
+    },
+    /// `handledAccessFs`, `handledAccessNet`, and `scoped` are all empty, so
+    /// this configuration restricts nothing.
+    #[error("ruleset handles no access right or scope")]
+    EmptyRuleset,
+    /// A `pathBeneath.parent` does not exist or could not be opened, found
+    /// by [`Config::validate_with_paths`].
+    #[error("{path}: {description}")]
+    PathNotFound { path: PathBuf, description: String },
+    /// A `pathBeneath` rule allows a directory-only access right (e.g.
+    /// `read_dir`, `make_reg`) against a parent that resolves to a regular
+    /// file rather than a directory, so the right can never be exercised.
+    /// Found by [`Config::validate_with_paths`].
+    #[error("pathBeneath rule for `{path}` allows `{access:?}`, which only applies to directories, but `{path}` is a regular file")]
+    DirectoryOnlyAccessOnFile {
+        path: PathBuf,
+        access: BitFlags<AccessFs>,
+    },
+}
+
+impl ValidationError {
+    /// The machine-readable code surfaced on this error's [`Diagnostic`], so
+    /// callers can match on it (e.g. to render a lint name or emit JSON)
+    /// without pattern-matching the variant itself.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::UnhandledPathAccess { .. } => "unhandled_path_access",
+            ValidationError::UnhandledNetAccess { .. } => "unhandled_net_access",
+            ValidationError::EmptyRuleset => "empty_ruleset",
+            ValidationError::PathNotFound { .. } => "path_not_found",
+            ValidationError::DirectoryOnlyAccessOnFile { .. } => "directory_only_access_on_file",
+        }
+    }
+}
+
+impl From<&ValidationError> for Diagnostic {
+    fn from(err: &ValidationError) -> Self {
+        let subject = match err {
+            ValidationError::UnhandledPathAccess { path, .. } => Some(path.clone()),
+            ValidationError::UnhandledNetAccess { port, .. } => Some(port.clone()),
+            ValidationError::EmptyRuleset => None,
+            ValidationError::PathNotFound { path, .. } => Some(path.display().to_string()),
+            ValidationError::DirectoryOnlyAccessOnFile { path, .. } => {
+                Some(path.display().to_string())
+            }
+        };
+        Diagnostic {
+            severity: Severity::Warning,
+            code: err.code(),
+            subject,
+            message: err.to_string(),
+        }
+    }
+}
+
+/// A single access right or rule dropped while downgrading a [`Config`] to
+/// an older ABI, see [`Config::resolve_for_abi`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DroppedRight {
+    pub description: String,
+    pub minimum_abi: ABI,
+}
+
+/// Report produced by [`Config::resolve_for_abi`], listing every access
+/// right and rule that had to be dropped to fit the target ABI.
+#[cfg_attr(test, derive(Default))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbiDowngradeReport {
+    pub dropped: Vec<DroppedRight>,
+}
+
+impl AbiDowngradeReport {
+    pub fn is_empty(&self) -> bool {
+        self.dropped.is_empty()
+    }
+}
+
+/// The Landlock ABI(s) a [`Config`] declares via its `abi` field: a single
+/// version, or a `{ min, max }` range. A range's effective handled-access
+/// sets are clamped to the intersection of what every ABI in `min..=max`
+/// supports, i.e. `min`'s own access set, since later ABIs are strict
+/// supersets of earlier ones (the same intersection
+/// [`Config::resolve_for_abi`] computes against a single target). `"latest"`
+/// in the source document resolves to the highest ABI this crate knows
+/// about and is stored as [`AbiRequirement::Exact`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbiRequirement {
+    Exact(ABI),
+    Range { min: ABI, max: ABI },
+}
+
+impl AbiRequirement {
+    /// The single ABI this requirement compares as "newer" against another,
+    /// following the same "higher wins" rule as
+    /// [`crate::parser::JsonConfig::union`]:
+    /// the version itself for `Exact`, or the top of the range for `Range`.
+    fn effective(&self) -> ABI {
+        match *self {
+            AbiRequirement::Exact(abi) => abi,
+            AbiRequirement::Range { max, .. } => max,
+        }
+    }
+}
+
+impl From<JsonAbiRange> for AbiRequirement {
+    fn from(range: JsonAbiRange) -> Self {
+        match range {
+            JsonAbiRange::Exact(abi) => AbiRequirement::Exact(abi.into()),
+            // Resolved the same way `ABI::from` clamps an out-of-range
+            // integer to this crate's highest known ABI, see `test_i32`.
+            JsonAbiRange::Latest => AbiRequirement::Exact(ABI::from(i32::MAX)),
+            JsonAbiRange::Range { min, max } => AbiRequirement::Range {
+                min: min.into(),
+                max: max.into(),
+            },
+        }
+    }
+}
+
+/// How strictly [`Config::build_ruleset_with_compat`] should treat access
+/// rights and rules the running kernel's Landlock ABI doesn't support,
+/// analogous to a protocol-version negotiation policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompatLevel {
+    /// Fail with [`BuildRulesetError::Unsupported`], identifying the first
+    /// unsupported right, instead of building a weaker ruleset.
+    HardRequirement,
+    /// Like [`CompatLevel::BestEffort`], but also fail if downgrading would
+    /// leave every handled access right dropped, since a ruleset that ends
+    /// up handling nothing silently enforces nothing.
+    SoftRequirement,
+    /// Downgrade to the detected ABI and build the reduced ruleset, like
+    /// [`Config::resolve_for_abi`]. Never fails because of a version
+    /// mismatch.
+    BestEffort,
+}
+
+/// The implicit level when a [`Config`]'s `compatibility` field is absent
+/// from its source document: silently downgrade rather than fail, matching
+/// [`Config::resolve_for_abi`]'s existing best-effort behavior.
+impl Default for CompatLevel {
+    fn default() -> Self {
+        CompatLevel::BestEffort
+    }
+}
+
+/// Selects where [`Config::resolve_with_source`] may look up a `${name}`
+/// reference that is not declared in the document's own `variable`
+/// section, analogous to cargo's config-from-environment mechanism.
+///
+/// This is the third, and narrowest, of the crate's environment-variable
+/// mechanisms, each solving a different part of "let the environment fill
+/// in a value" and each applying at a different stage:
+///
+/// * `${env:NAME}` template syntax (see
+///   [`crate::variable::TemplateString::expand`]) reads the raw process
+///   environment directly wherever it appears in a template string, no
+///   opt-in required.
+/// * [`Config::parse_json_with_env`]/[`Config::parse_toml_with_env`] fill
+///   in a declared `variable` entry's missing `literal` from an environment
+///   variable of that entry's own name, at parse time, before the document
+///   becomes a [`Config`].
+/// * [`VariableSource::ConfigAndEnv`] instead lets an *undeclared* `${name}`
+///   reference fall back to the namespaced `LANDLOCKCONFIG_<NAME>` variable,
+///   at resolve time. The `LANDLOCKCONFIG_` prefix keeps this allowlisted
+///   fallback from colliding with unrelated environment variables that
+///   happen to share a short variable name like `HOME`.
+///
+/// These don't overlap in practice: `${env:NAME}` is a distinct template
+/// form from `${name}`, and `parse_*_with_env` only ever fills in a
+/// `literal` that's already declared, so an undeclared `${name}` only ever
+/// reaches [`VariableSource::ConfigAndEnv`]'s fallback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VariableSource {
+    /// Only the document's `variable` section is consulted; an undeclared
+    /// `${name}` is a [`ResolveError::VariableNotFound`]. The implicit mode
+    /// for [`Config::resolve`]/[`Config::resolve_with_limits`], so a
+    /// sandboxing config can't silently absorb arbitrary environment state
+    /// just by being resolved.
+    ConfigOnly,
+    /// Falls back to the process environment variable named
+    /// `LANDLOCKCONFIG_<NAME>` (split into the crate's existing multi-value
+    /// list form on `:`, like [`crate::parser::JsonConfig::resolve_env_variables`])
+    /// when `name` is not declared in the document's `variable` section.
+    /// An undeclared, unset name is a
+    /// [`ResolveError::VariableNotFoundInConfigOrEnv`].
+    ConfigAndEnv,
+}
+
+impl Default for VariableSource {
+    fn default() -> Self {
+        VariableSource::ConfigOnly
+    }
+}
+
+/// Selects how [`Config::compose_with`] reconciles two configurations; see
+/// [`Config::merge`] for a related, ABI-aware layering alternative that
+/// additionally rejects conflicting `abi` requirements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComposeMode {
+    /// Downgrade to the common handled access rights, drop rule access no
+    /// longer covered, and remove rules left with none, see
+    /// [`Config::compose`]. Commutative for handled access, scopes, rules
+    /// and variables: `a.compose_with(&b, Intersect)` and
+    /// `b.compose_with(&a, Intersect)` agree on those, while `abi` and
+    /// `compatibility` keep the receiver's own value.
+    Intersect,
+    /// Widen to the union of handled access rights and scopes, and
+    /// accumulate every rule from both sides without dropping any, for
+    /// assembling fragments that all target a single, already-known ABI.
+    /// Also commutative for handled access, scopes, rules and variables: OR
+    /// and [`std::collections::BTreeMap`] union don't depend on argument
+    /// order. `abi` and `compatibility` keep the receiver's own value.
+    Union,
+}
+
+impl Default for ComposeMode {
+    fn default() -> Self {
+        ComposeMode::Intersect
+    }
+}
+
+impl From<JsonCompatLevel> for CompatLevel {
+    fn from(level: JsonCompatLevel) -> Self {
+        match level {
+            JsonCompatLevel::BestEffort => CompatLevel::BestEffort,
+            JsonCompatLevel::SoftRequirement => CompatLevel::SoftRequirement,
+            JsonCompatLevel::HardRequirement => CompatLevel::HardRequirement,
+        }
+    }
+}
+
+impl From<CompatLevel> for JsonCompatLevel {
+    fn from(level: CompatLevel) -> Self {
+        match level {
+            CompatLevel::BestEffort => JsonCompatLevel::BestEffort,
+            CompatLevel::SoftRequirement => JsonCompatLevel::SoftRequirement,
+            CompatLevel::HardRequirement => JsonCompatLevel::HardRequirement,
+        }
+    }
+}
+
+/// Returns the highest minimum ABI required among the set bits of `access`,
+/// i.e. the ABI a rule using all of them would need.
+fn max_minimum_abi<A>(access: BitFlags<A>, minimum_abi: impl Fn(A) -> ABI) -> ABI
+where
+    A: landlock::Access,
+{
+    access.iter().fold(ABI::V1, |max_abi, right| {
+        let abi = minimum_abi(right);
+        if abi > max_abi {
+            abi
+        } else {
+            max_abi
+        }
+    })
 }
 
 impl TryFrom<NonEmptyStruct<JsonConfig>> for Config {
     type Error = ConfigError;
 
     fn try_from(json: NonEmptyStruct<JsonConfig>) -> Result<Self, Self::Error> {
+        json.into_inner().try_into()
+    }
+}
+
+impl TryFrom<JsonConfig> for Config {
+    type Error = ConfigError;
+
+    fn try_from(json: JsonConfig) -> Result<Self, Self::Error> {
         let mut config = Self::empty();
-        let json = json.into_inner();
+
+        config.abi = json.abi.map(AbiRequirement::from);
+        config.compatibility = json
+            .compatibility
+            .map(CompatLevel::from)
+            .unwrap_or_default();
 
         for variable in json.variable.unwrap_or_default() {
             let name = variable.name.parse()?;
@@ -118,6 +624,10 @@ impl TryFrom<NonEmptyStruct<JsonConfig>> for Config {
             }
         }
 
+        if let Some(AbiRequirement::Range { min, .. }) = config.abi {
+            config.downgrade_to_abi(min);
+        }
+
         Ok(config)
     }
 }
@@ -125,8 +635,33 @@ impl TryFrom<NonEmptyStruct<JsonConfig>> for Config {
 #[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum RuleError {
-    #[error(transparent)]
-    PathFd(#[from] PathFdError),
+    #[error("{path}: {source}")]
+    PathFd {
+        path: PathBuf,
+        #[source]
+        source: PathFdError,
+    },
+}
+
+impl RuleError {
+    /// The path/port this error refers to, used as the `subject` of its
+    /// [`Diagnostic`] conversion.
+    fn subject(&self) -> String {
+        match self {
+            RuleError::PathFd { path, .. } => path.display().to_string(),
+        }
+    }
+}
+
+impl From<&RuleError> for Diagnostic {
+    fn from(err: &RuleError) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            code: "rule_error",
+            subject: Some(err.subject()),
+            message: err.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -137,6 +672,48 @@ pub enum ParseJsonError {
     SerdeJson(#[from] serde_json::Error),
 }
 
+impl ParseJsonError {
+    /// Returns the coarse [`serde_json::error::Category`] for this error,
+    /// for callers that only need a broad classification.
+    pub fn classify(&self) -> serde_json::error::Category {
+        match self {
+            ParseJsonError::Config(_) => serde_json::error::Category::Data,
+            ParseJsonError::SerdeJson(e) => e.classify(),
+        }
+    }
+
+    /// Builds a structured [`ParseDiagnostic`] for this error, with source
+    /// location and accepted-variant information suitable for editor/CI
+    /// integration. See [`ParseDiagnostic`] for the level of detail
+    /// available.
+    pub fn diagnostic(&self) -> ParseDiagnostic {
+        match self {
+            ParseJsonError::Config(e) => ParseDiagnostic {
+                kind: DiagnosticKind::Other,
+                message: e.to_string(),
+                path: None,
+                line: None,
+                col: None,
+                expected: None,
+                span: None,
+            },
+            ParseJsonError::SerdeJson(e) => e.into(),
+        }
+    }
+}
+
+impl From<&ParseJsonError> for Diagnostic {
+    fn from(err: &ParseJsonError) -> Self {
+        let diagnostic = err.diagnostic();
+        Diagnostic {
+            severity: Severity::Error,
+            code: "parse_json_error",
+            subject: diagnostic.path.clone(),
+            message: diagnostic.to_string(),
+        }
+    }
+}
+
 #[cfg(feature = "toml")]
 #[derive(Debug, Error)]
 pub enum ParseTomlError {
@@ -146,6 +723,53 @@ pub enum ParseTomlError {
     SerdeToml(#[from] toml::de::Error),
 }
 
+#[cfg(feature = "toml")]
+impl ParseTomlError {
+    /// Builds a structured [`ParseDiagnostic`] for this error. See
+    /// [`ParseJsonError::diagnostic`].
+    pub fn diagnostic(&self) -> ParseDiagnostic {
+        match self {
+            ParseTomlError::Config(e) => ParseDiagnostic {
+                kind: DiagnosticKind::Other,
+                message: e.to_string(),
+                path: None,
+                line: None,
+                col: None,
+                expected: None,
+                span: None,
+            },
+            ParseTomlError::SerdeToml(e) => e.into(),
+        }
+    }
+
+    /// Like [`ParseTomlError::diagnostic`], but additionally resolves the
+    /// line/column of the failure by walking `source`: `toml::de::Error`
+    /// only ever exposes its location as a byte span, since line/column
+    /// accounting would otherwise require it to hold onto the original text.
+    pub fn diagnostic_with_source(&self, source: &str) -> ParseDiagnostic {
+        let mut diagnostic = self.diagnostic();
+        if let Some(span) = &diagnostic.span {
+            let (line, col) = line_col_from_offset(source, span.start);
+            diagnostic.line = Some(line);
+            diagnostic.col = Some(col);
+        }
+        diagnostic
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<&ParseTomlError> for Diagnostic {
+    fn from(err: &ParseTomlError) -> Self {
+        let diagnostic = err.diagnostic();
+        Diagnostic {
+            severity: Severity::Error,
+            code: "parse_toml_error",
+            subject: diagnostic.path.clone(),
+            message: diagnostic.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ParseFileError {
     #[error(transparent)]
@@ -157,6 +781,22 @@ pub enum ParseFileError {
     ParseToml(#[from] ParseTomlError),
 }
 
+impl From<&ParseFileError> for Diagnostic {
+    fn from(err: &ParseFileError) -> Self {
+        match err {
+            ParseFileError::Io(e) => Diagnostic {
+                severity: Severity::Error,
+                code: "io_error",
+                subject: None,
+                message: e.to_string(),
+            },
+            ParseFileError::ParseJson(e) => e.into(),
+            #[cfg(feature = "toml")]
+            ParseFileError::ParseToml(e) => e.into(),
+        }
+    }
+}
+
 fn format_parse_files_error(errors: &BTreeMap<PathBuf, ParseFileError>) -> String {
     errors
         .iter()
@@ -174,6 +814,41 @@ pub enum ParseDirectoryError {
     ParseFiles(BTreeMap<PathBuf, ParseFileError>),
     #[error("no configuration file found")]
     NoConfigFile,
+    /// Raised by [`Config::parse_directory_recursive`] when a directory
+    /// contains both `{stem}.json` and `{stem}.toml`: which one should
+    /// take precedence over the other is ambiguous, so this is a hard
+    /// error instead of picking one by convention.
+    #[cfg(feature = "toml")]
+    #[error("ambiguous configuration fragment `{}`: found as both `{}` and `{}`", stem.display(), json.display(), toml.display())]
+    AmbiguousSource {
+        stem: PathBuf,
+        json: PathBuf,
+        toml: PathBuf,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum ParseIncludeError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    ParseJson(#[from] ParseJsonError),
+    #[error("circular include: `{0}` is already being loaded")]
+    Cycle(PathBuf),
+    #[error(transparent)]
+    Merge(#[from] MergeError),
+}
+
+/// Error from [`Config::merge`] when two layers declare different explicit
+/// `abi` requirements that can't be reconciled by picking one, unlike every
+/// other field `merge` combines unconditionally.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum MergeError {
+    #[error("conflicting abi requirement: {a:?} vs {b:?}")]
+    ConflictingAbi {
+        a: AbiRequirement,
+        b: AbiRequirement,
+    },
 }
 
 pub enum ConfigFormat {
@@ -192,19 +867,111 @@ impl ConfigFormat {
     }
 }
 
+/// Extension point for feeding another serialization format into the same
+/// [`JsonConfig`] schema, following the `config` crate's `custom_format`
+/// pattern: anything that can turn raw text into a [`JsonConfig`] can be
+/// registered with [`Config::parse_source`] without `Config` or the schema
+/// itself needing to change. [`JsonFormat`] and [`TomlFormat`] are
+/// themselves just the two built-in implementations; a YAML or JSON5
+/// source behind its own feature flag (mirroring how `toml` is gated here)
+/// would be a third.
+pub(crate) trait FormatSource {
+    type Error;
+
+    fn parse(&self, input: &str) -> Result<JsonConfig, Self::Error>;
+}
+
+pub(crate) struct JsonFormat;
+
+impl FormatSource for JsonFormat {
+    type Error = ParseJsonError;
+
+    fn parse(&self, input: &str) -> Result<JsonConfig, Self::Error> {
+        Ok(serde_json::from_str::<NonEmptyStruct<JsonConfig>>(input)?.into_inner())
+    }
+}
+
+#[cfg(feature = "toml")]
+pub(crate) struct TomlFormat;
+
+#[cfg(feature = "toml")]
+impl FormatSource for TomlFormat {
+    type Error = ParseTomlError;
+
+    fn parse(&self, input: &str) -> Result<JsonConfig, Self::Error> {
+        Ok(toml::from_str::<NonEmptyStruct<TomlConfig>>(input)?
+            .into_inner()
+            .into())
+    }
+}
+
 impl Config {
     // Do not implement Default for Config because it would not be useful but
     // misleading.  Indeed, the default configuration would allow everything and
     // could not be updated with public methods (e.g. compose).
-    fn empty() -> Self {
+    pub(crate) fn empty() -> Self {
         Self {
+            abi: None,
+            compatibility: CompatLevel::default(),
             variables: Default::default(),
             handled_fs: Default::default(),
             handled_net: Default::default(),
             scoped: Default::default(),
             rules_path_beneath: Default::default(),
             rules_net_port: Default::default(),
+            provenance: Default::default(),
+        }
+    }
+
+    /// Tags every access right and rule currently set on this
+    /// configuration as coming from `source`. This is the opt-in for
+    /// provenance tracking: until a `Config` has been tagged this way
+    /// (directly, or through one of the configs [`Config::compose`]d or
+    /// [`Config::merge`]d into it), every `origins_for_*` query on it
+    /// returns an empty slice, and tracking costs nothing. Typically called
+    /// right after parsing a single file, before composing it with others,
+    /// e.g. `Config::parse_json_file(&path)?.with_source(Source::File(path))`.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.provenance.record_fs(self.handled_fs, &source);
+        self.provenance.record_net(self.handled_net, &source);
+        self.provenance.record_scope(self.scoped, &source);
+        for path in self.rules_path_beneath.keys() {
+            self.provenance.record_path(path, &source);
+        }
+        for port in self.rules_net_port.keys() {
+            self.provenance.record_port(*port, &source);
         }
+        self
+    }
+
+    /// Sources tagged via [`Config::with_source`] that contributed the
+    /// `pathBeneath` rule for this exact, pre-resolution template; empty if
+    /// no source tagged it. See [`ResolvedConfig::origins_for_path`] for
+    /// querying by the concrete path a rule resolved to.
+    pub fn origins_for_rule(&self, path: &TemplateString) -> &[Source] {
+        self.provenance
+            .rules_path_beneath
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Sources tagged via [`Config::with_source`] that set `access` in
+    /// `handledAccessFs`.
+    pub fn origins_for_access(&self, access: AccessFs) -> &[Source] {
+        origins_for_fs(&self.provenance.handled_fs, access)
+    }
+
+    /// Sources tagged via [`Config::with_source`] that set `access` in
+    /// `handledAccessNet`.
+    pub fn origins_for_net_access(&self, access: AccessNet) -> &[Source] {
+        origins_for_net(&self.provenance.handled_net, access)
+    }
+
+    /// Sources tagged via [`Config::with_source`] that set `scope` in
+    /// `scoped`.
+    pub fn origins_for_scope(&self, scope: Scope) -> &[Source] {
+        origins_for_scope(&self.provenance.scoped, scope)
     }
 
     /// Composes two configurations by merging `other` with `self` in a safe
@@ -230,18 +997,47 @@ impl Config {
     /// configuration, ensuring predictable behavior regardless of the sequence
     /// in which configurations are combined.
     pub fn compose(&mut self, other: &Self) {
+        self.compose_with(other, ComposeMode::Intersect);
+    }
+
+    /// Like [`Config::compose`], but accepts `mode` to pick between the
+    /// intersection downgrade [`Config::compose`] performs
+    /// ([`ComposeMode::Intersect`]) and an additive union
+    /// ([`ComposeMode::Union`]) that widens handled access and accumulates
+    /// every rule instead, for assembling fragments that all target a
+    /// single, already-known ABI.
+    pub fn compose_with(&mut self, other: &Self, mode: ComposeMode) {
+        match mode {
+            ComposeMode::Intersect => self.compose_intersect(other),
+            ComposeMode::Union => self.compose_union(other),
+        }
+    }
+
+    fn compose_intersect(&mut self, other: &Self) {
         let common_handled_fs = self.handled_fs & other.handled_fs;
         let common_handled_net = self.handled_net & other.handled_net;
+        let common_scoped = self.scoped & other.scoped;
 
         // First step: downgrade the current access rights according to other's
         // handled access rights, and remove entries with empty accesses.
-        self.rules_path_beneath.retain(|_, access| {
+        let provenance = &mut self.provenance;
+        self.rules_path_beneath.retain(|path, access| {
             *access &= common_handled_fs;
-            !access.is_empty()
+            if access.is_empty() {
+                provenance.rules_path_beneath.remove(path);
+                false
+            } else {
+                true
+            }
         });
-        self.rules_net_port.retain(|_, access| {
+        self.rules_net_port.retain(|port, access| {
             *access &= common_handled_net;
-            !access.is_empty()
+            if access.is_empty() {
+                provenance.rules_net_port.remove(port);
+                false
+            } else {
+                true
+            }
         });
 
         // Second step: add the new rules from other, downgrade according to
@@ -253,6 +1049,16 @@ impl Config {
                     .entry(path.clone())
                     .and_modify(|a| *a |= downgraded_access)
                     .or_insert(downgraded_access);
+                if let Some(sources) = other.provenance.rules_path_beneath.get(path) {
+                    let entry = self
+                        .provenance
+                        .rules_path_beneath
+                        .entry(path.clone())
+                        .or_default();
+                    for source in sources {
+                        add_source(entry, source);
+                    }
+                }
             }
         }
         for (port, access) in &other.rules_net_port {
@@ -262,6 +1068,12 @@ impl Config {
                     .entry(*port)
                     .and_modify(|a| *a |= downgraded_access)
                     .or_insert(downgraded_access);
+                if let Some(sources) = other.provenance.rules_net_port.get(port) {
+                    let entry = self.provenance.rules_net_port.entry(*port).or_default();
+                    for source in sources {
+                        add_source(entry, source);
+                    }
+                }
             }
         }
 
@@ -270,31 +1082,306 @@ impl Config {
             self.variables.extend(name.clone(), value.clone());
         }
 
-        // Fourth step: downgrade the handled access rights.
-        self.handled_fs &= other.handled_fs;
-        self.handled_net &= other.handled_net;
-        self.scoped &= other.scoped;
+        // Fourth step: downgrade the handled access rights, and compose
+        // their provenance the same way: drop sources for any right that
+        // fell out of the intersection, union in `other`'s sources for any
+        // right that's still handled.
+        self.provenance
+            .compose_fs(common_handled_fs, &other.provenance.handled_fs);
+        self.provenance
+            .compose_net(common_handled_net, &other.provenance.handled_net);
+        self.provenance
+            .compose_scoped(common_scoped, &other.provenance.scoped);
+        self.handled_fs = common_handled_fs;
+        self.handled_net = common_handled_net;
+        self.scoped = common_scoped;
     }
 
-    pub fn parse_json<R>(reader: R) -> Result<Self, ParseJsonError>
-    where
-        R: std::io::Read,
-    {
-        let json = serde_json::from_reader::<_, NonEmptyStruct<JsonConfig>>(reader)?;
-        Ok(json.try_into()?)
-    }
+    /// [`ComposeMode::Union`] half of [`Config::compose_with`]: OR-combines
+    /// handled access rights and scopes instead of intersecting them, and
+    /// accumulates every rule from both sides without the intersect mode's
+    /// empty-removal retain pass. Unlike [`Config::merge`], `abi` is left
+    /// untouched, matching the intersect mode's existing behavior.
+    fn compose_union(&mut self, other: &Self) {
+        let union_handled_fs = self.handled_fs | other.handled_fs;
+        let union_handled_net = self.handled_net | other.handled_net;
+        let union_scoped = self.scoped | other.scoped;
 
-    #[cfg(feature = "toml")]
-    pub fn parse_toml(data: &str) -> Result<Self, ParseTomlError> {
-        // The TOML parser does not handle Read implementations,
-        // see https://github.com/toml-rs/toml/issues/326
-        let json: NonEmptyStruct<JsonConfig> =
-            toml::from_str::<NonEmptyStruct<TomlConfig>>(data)?.convert();
-        Ok(json.try_into()?)
-    }
+        for (path, access) in &other.rules_path_beneath {
+            self.rules_path_beneath
+                .entry(path.clone())
+                .and_modify(|a| *a |= *access)
+                .or_insert(*access);
+            if let Some(sources) = other.provenance.rules_path_beneath.get(path) {
+                let entry = self
+                    .provenance
+                    .rules_path_beneath
+                    .entry(path.clone())
+                    .or_default();
+                for source in sources {
+                    add_source(entry, source);
+                }
+            }
+        }
+        for (port, access) in &other.rules_net_port {
+            self.rules_net_port
+                .entry(*port)
+                .and_modify(|a| *a |= *access)
+                .or_insert(*access);
+            if let Some(sources) = other.provenance.rules_net_port.get(port) {
+                let entry = self.provenance.rules_net_port.entry(*port).or_default();
+                for source in sources {
+                    add_source(entry, source);
+                }
+            }
+        }
 
-    /// Parse all configuration files in a directory with the specified format.
-    ///
+        for (name, value) in other.variables.iter() {
+            self.variables.extend(name.clone(), value.clone());
+        }
+
+        // Reusing compose_fs/compose_net/compose_scoped with the union mask
+        // is safe here: the mask is a superset of self's own flags, so the
+        // retain step inside them drops nothing, while still unioning in
+        // other's sources for every flag, new or shared.
+        self.provenance
+            .compose_fs(union_handled_fs, &other.provenance.handled_fs);
+        self.provenance
+            .compose_net(union_handled_net, &other.provenance.handled_net);
+        self.provenance
+            .compose_scoped(union_scoped, &other.provenance.scoped);
+        self.handled_fs = union_handled_fs;
+        self.handled_net = union_handled_net;
+        self.scoped = union_scoped;
+    }
+
+    /// Merges `other` into `self` by taking the union of everything, unlike
+    /// the intersection-based downgrade that [`Config::compose`] performs.
+    ///
+    /// # Behavior
+    ///
+    /// - Handled access rights and scopes are combined using bitwise OR
+    ///   (union).
+    /// - Rules are merged with OR-ed access sets, the same way two identical
+    ///   paths or ports within a single configuration are already
+    ///   deduplicated.
+    /// - Variables from both configurations are merged.
+    /// - An explicit `abi` present on only one side is kept; present on
+    ///   both sides, it must be the same requirement on each, or this
+    ///   returns [`MergeError::ConflictingAbi`] rather than silently
+    ///   picking one - unlike handled access and rules, there's no sound
+    ///   way to OR two different ABI requirements together.
+    /// - Provenance recorded via [`Config::with_source`] is combined the
+    ///   same way [`Config::compose_with`]'s [`ComposeMode::Union`] does, so
+    ///   `origins_for_access`/`origins_for_rule`/`origins_for_scope` still
+    ///   report the right layer after merging.
+    ///
+    /// Intended for layering a base policy with per-app overlays, see
+    /// [`Config::from_layers`] and [`Config::parse_json_file`].
+    pub fn merge(&mut self, other: &Self) -> Result<(), MergeError> {
+        let union_handled_fs = self.handled_fs | other.handled_fs;
+        let union_handled_net = self.handled_net | other.handled_net;
+        let union_scoped = self.scoped | other.scoped;
+
+        for (parent, access) in &other.rules_path_beneath {
+            self.rules_path_beneath
+                .entry(parent.clone())
+                .and_modify(|a| *a |= *access)
+                .or_insert(*access);
+            if let Some(sources) = other.provenance.rules_path_beneath.get(parent) {
+                let entry = self
+                    .provenance
+                    .rules_path_beneath
+                    .entry(parent.clone())
+                    .or_default();
+                for source in sources {
+                    add_source(entry, source);
+                }
+            }
+        }
+        for (port, access) in &other.rules_net_port {
+            self.rules_net_port
+                .entry(*port)
+                .and_modify(|a| *a |= *access)
+                .or_insert(*access);
+            if let Some(sources) = other.provenance.rules_net_port.get(port) {
+                let entry = self.provenance.rules_net_port.entry(*port).or_default();
+                for source in sources {
+                    add_source(entry, source);
+                }
+            }
+        }
+
+        for (name, value) in other.variables.iter() {
+            self.variables.extend(name.clone(), value.clone());
+        }
+
+        self.abi = match (self.abi, other.abi) {
+            (Some(a), Some(b)) if a == b => Some(a),
+            (Some(a), Some(b)) => return Err(MergeError::ConflictingAbi { a, b }),
+            (a, None) => a,
+            (None, b) => b,
+        };
+
+        // Reusing compose_fs/compose_net/compose_scoped with the union mask
+        // is safe here, the same way compose_union does: the mask is a
+        // superset of self's own flags, so their retain step drops nothing,
+        // while still unioning in other's sources for every flag, new or
+        // shared.
+        self.provenance
+            .compose_fs(union_handled_fs, &other.provenance.handled_fs);
+        self.provenance
+            .compose_net(union_handled_net, &other.provenance.handled_net);
+        self.provenance
+            .compose_scoped(union_scoped, &other.provenance.scoped);
+        self.handled_fs = union_handled_fs;
+        self.handled_net = union_handled_net;
+        self.scoped = union_scoped;
+        Ok(())
+    }
+
+    /// Builds a single [`Config`] by merging every layer in order with
+    /// [`Config::merge`], the base policy first and the most specific
+    /// overlay last.
+    pub fn from_layers<I>(layers: I) -> Result<Self, MergeError>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        let mut config = Self::empty();
+        for layer in layers {
+            config.merge(&layer)?;
+        }
+        Ok(config)
+    }
+
+    /// Parses `input` with any [`FormatSource`], JSON and TOML being the
+    /// two built-in ones ([`JsonFormat`], [`TomlFormat`]). This is the
+    /// entry point a crate-internal format added behind its own feature
+    /// flag (e.g. YAML, JSON5) would plug into, rather than [`Config`]
+    /// growing a bespoke `parse_*` pair for it.
+    pub(crate) fn parse_source<S>(source: &S, input: &str) -> Result<Self, S::Error>
+    where
+        S: FormatSource,
+        S::Error: From<ConfigError>,
+    {
+        Ok(source.parse(input)?.try_into()?)
+    }
+
+    pub fn parse_json<R>(reader: R) -> Result<Self, ParseJsonError>
+    where
+        R: std::io::Read,
+    {
+        let json = serde_json::from_reader::<_, NonEmptyStruct<JsonConfig>>(reader)?;
+        Ok(json.try_into()?)
+    }
+
+    /// Like [`Config::parse_json`], but first selects `profile` from the
+    /// document's top-level `profiles` list and unions its overrides onto
+    /// the base `ruleset`/`pathBeneath`/`netPort` before building the
+    /// [`Config`], see [`crate::parser::JsonConfig::select_profile`]. Fails
+    /// with [`ConfigError::Profile`] if no profile named `profile` is
+    /// declared.
+    pub fn parse_json_with_profile<R>(reader: R, profile: &str) -> Result<Self, ParseJsonError>
+    where
+        R: std::io::Read,
+    {
+        let json = serde_json::from_reader::<_, NonEmptyStruct<JsonConfig>>(reader)?;
+        let json = json
+            .into_inner()
+            .select_profile(profile)
+            .map_err(ConfigError::from)?;
+        Ok(json.try_into()?)
+    }
+
+    /// Like [`Config::parse_json`], but fills in any `variable` entry whose
+    /// `literal` is absent from the environment variable of the same name.
+    /// See [`crate::parser::JsonConfig::resolve_env_variables`] and
+    /// [`VariableSource`] for how this relates to the crate's other two
+    /// environment-variable mechanisms.
+    pub fn parse_json_with_env<R>(reader: R) -> Result<Self, ParseJsonError>
+    where
+        R: std::io::Read,
+    {
+        let json = serde_json::from_reader::<_, NonEmptyStruct<JsonConfig>>(reader)?;
+        let json = json
+            .into_inner()
+            .resolve_env_variables()
+            .map_err(ConfigError::from)?;
+        Ok(json.try_into()?)
+    }
+
+    /// Like [`Config::parse_json`], but also pushes a [`Diagnostic`]
+    /// describing the failure into `diagnostics` on error, for callers (e.g.
+    /// across the C FFI) that surface structured diagnostics instead of only
+    /// a `Result`.
+    pub fn parse_json_with_diagnostics<R>(
+        reader: R,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Self, ParseJsonError>
+    where
+        R: std::io::Read,
+    {
+        let result = Self::parse_json(reader);
+        if let Err(e) = &result {
+            diagnostics.push(e.into());
+        }
+        result
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn parse_toml(data: &str) -> Result<Self, ParseTomlError> {
+        // The TOML parser does not handle Read implementations,
+        // see https://github.com/toml-rs/toml/issues/326
+        let json: NonEmptyStruct<JsonConfig> =
+            toml::from_str::<NonEmptyStruct<TomlConfig>>(data)?.convert();
+        Ok(json.try_into()?)
+    }
+
+    /// Like [`Config::parse_toml`], but first selects `profile` the same way
+    /// [`Config::parse_json_with_profile`] does.
+    #[cfg(feature = "toml")]
+    pub fn parse_toml_with_profile(data: &str, profile: &str) -> Result<Self, ParseTomlError> {
+        let json: JsonConfig = toml::from_str::<NonEmptyStruct<TomlConfig>>(data)?
+            .into_inner()
+            .into();
+        let json = json.select_profile(profile).map_err(ConfigError::from)?;
+        Ok(json.try_into()?)
+    }
+
+    /// Like [`Config::parse_toml`], but resolves environment-backed
+    /// variables the same way [`Config::parse_json_with_env`] does.
+    #[cfg(feature = "toml")]
+    pub fn parse_toml_with_env(data: &str) -> Result<Self, ParseTomlError> {
+        let json: JsonConfig = toml::from_str::<NonEmptyStruct<TomlConfig>>(data)?
+            .into_inner()
+            .into();
+        let json = json.resolve_env_variables().map_err(ConfigError::from)?;
+        Ok(json.try_into()?)
+    }
+
+    /// Like [`Config::parse_toml`], but also pushes a [`Diagnostic`]
+    /// describing the failure into `diagnostics` on error. See
+    /// [`Config::parse_json_with_diagnostics`].
+    #[cfg(feature = "toml")]
+    pub fn parse_toml_with_diagnostics(
+        data: &str,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Self, ParseTomlError> {
+        let result = Self::parse_toml(data);
+        if let Err(e) = &result {
+            let diagnostic = e.diagnostic_with_source(data);
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: "parse_toml_error",
+                subject: diagnostic.path.clone(),
+                message: diagnostic.to_string(),
+            });
+        }
+        result
+    }
+
+    /// Parse all configuration files in a directory with the specified format.
+    ///
     /// This method reads all files in the given directory that match the specified
     /// format's file extension and are regular files (not directories or hidden files
     /// starting with '.'). Each valid configuration file is parsed and composed into
@@ -305,6 +1392,22 @@ impl Config {
     /// Returns a composed `Config` from all valid configuration files found in the
     /// directory, or ParseDirectoryError otherwise.
     pub fn parse_directory<T>(path: T, format: ConfigFormat) -> Result<Self, ParseDirectoryError>
+    where
+        T: AsRef<Path>,
+    {
+        Self::parse_directory_with_mode(path, format, ComposeMode::Intersect)
+    }
+
+    /// Like [`Config::parse_directory`], but accepts `mode` to pick how
+    /// each file is composed into the running total, e.g.
+    /// `Config::parse_directory_with_mode(dir, format, ComposeMode::Union)`
+    /// to assemble fragments that all target a single, already-known ABI
+    /// without any of them downgrading the others.
+    pub fn parse_directory_with_mode<T>(
+        path: T,
+        format: ConfigFormat,
+        mode: ComposeMode,
+    ) -> Result<Self, ParseDirectoryError>
     where
         T: AsRef<Path>,
     {
@@ -332,7 +1435,8 @@ impl Config {
             match format {
                 ConfigFormat::Json => match File::open(&path) {
                     Ok(file) => match Self::parse_json(file) {
-                        Ok(config) => full_config.compose(&config),
+                        Ok(config) => full_config
+                            .compose_with(&config.with_source(Source::File(path.clone())), mode),
                         Err(e) => {
                             // Duplicated file names should be very rare when
                             // listing the content of a directory, and ignoring
@@ -350,7 +1454,8 @@ impl Config {
                 #[cfg(feature = "toml")]
                 ConfigFormat::Toml => match std::fs::read_to_string(&path) {
                     Ok(data) => match Self::parse_toml(data.as_str()) {
-                        Ok(config) => full_config.compose(&config),
+                        Ok(config) => full_config
+                            .compose_with(&config.with_source(Source::File(path.clone())), mode),
                         Err(e) => {
                             errors.insert(path.clone(), e.into());
                         }
@@ -370,171 +1475,2917 @@ impl Config {
         full_config.ok_or(ParseDirectoryError::NoConfigFile)
     }
 
-    pub fn resolve(self) -> Result<ResolvedConfig, ResolveError> {
-        self.try_into()
+    /// Like [`Config::parse_directory`], but also descends into
+    /// subdirectories and accepts both JSON and TOML fragments (when the
+    /// `toml` feature is enabled) side by side, picking the format per file
+    /// from its extension instead of a single fixed [`ConfigFormat`].
+    ///
+    /// Directories are visited in a deterministic, documented order: each
+    /// directory's entries are sorted by name and walked depth-first, a
+    /// directory's own files composing before its subdirectories'. Because
+    /// [`compose`](Config::compose) is commutative and idempotent, this
+    /// traversal order does not change the resulting configuration; it only
+    /// makes the result reproducible to reason about and test. Dotfiles and
+    /// dot-directories are skipped, same as [`Config::parse_directory`].
+    pub fn parse_directory_recursive<T>(path: T) -> Result<Self, ParseDirectoryError>
+    where
+        T: AsRef<Path>,
+    {
+        Self::parse_directory_recursive_with_mode(path, ComposeMode::Intersect)
     }
-}
-
-#[test]
-fn test_config_default_empty() {
-    let config = Config::default();
-    assert_eq!(config.handled_fs, BitFlags::EMPTY);
-    assert_eq!(config.handled_net, BitFlags::EMPTY);
-    assert_eq!(config.scoped, BitFlags::EMPTY);
-
-    assert_eq!(config, Config::empty());
-}
 
-pub trait OptionalConfig {
-    fn compose(&mut self, other: &Config);
-}
+    /// Like [`Config::parse_directory_recursive`], but accepts `mode` to
+    /// pick how each file is composed into the running total, mirroring
+    /// [`Config::parse_directory_with_mode`].
+    pub fn parse_directory_recursive_with_mode<T>(
+        path: T,
+        mode: ComposeMode,
+    ) -> Result<Self, ParseDirectoryError>
+    where
+        T: AsRef<Path>,
+    {
+        let mut full_config = None;
+        let mut errors = BTreeMap::new();
+        Self::collect_directory_recursive(path.as_ref(), mode, &mut full_config, &mut errors)?;
 
-impl OptionalConfig for Option<Config> {
-    fn compose(&mut self, other: &Config) {
-        match self {
-            Some(config) => config.compose(other),
-            None => *self = Some(other.clone()),
+        if !errors.is_empty() {
+            return Err(ParseDirectoryError::ParseFiles(errors));
         }
+        full_config.ok_or(ParseDirectoryError::NoConfigFile)
     }
-}
 
-impl ResolvedConfig {
-    pub fn build_ruleset(&self) -> Result<(RulesetCreated, Vec<RuleError>), BuildRulesetError> {
-        let mut ruleset = Ruleset::default();
-        let ruleset_ref = &mut ruleset;
-        if !self.handled_fs.is_empty() {
-            ruleset_ref.handle_access(self.handled_fs)?;
-        }
-        if !self.handled_net.is_empty() {
-            ruleset_ref.handle_access(self.handled_net)?;
-        }
-        if !self.scoped.is_empty() {
-            ruleset_ref.scope(self.scoped)?;
-        }
-        let mut ruleset_created = ruleset.create()?;
-        let ruleset_created_ref = &mut ruleset_created;
-        let mut rule_errors = Vec::new();
+    fn collect_directory_recursive(
+        dir: &Path,
+        mode: ComposeMode,
+        full_config: &mut Option<Config>,
+        errors: &mut BTreeMap<PathBuf, ParseFileError>,
+    ) -> Result<(), ParseDirectoryError> {
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<_, std::io::Error>>()?;
+        entries.sort();
 
-        for (parent, allowed_access) in &self.rules_path_beneath {
-            // TODO: Walk through all path and only open them once, including their
-            // common parent directory to get a consistent hierarchy.
-            let fd = match PathFd::new(parent) {
-                Ok(fd) => fd,
-                Err(e) => {
-                    rule_errors.push(RuleError::PathFd(e));
-                    continue;
-                }
+        #[cfg(feature = "toml")]
+        let mut seen_stems: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+
+        for path in entries {
+            if path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| name.starts_with('.'))
+            {
+                continue;
+            }
+
+            if path.is_dir() {
+                Self::collect_directory_recursive(&path, mode, full_config, errors)?;
+                continue;
+            }
+
+            if !path.is_file() {
+                continue;
+            }
+
+            let extension = path.extension().and_then(|e| e.to_str());
+            let format = match extension {
+                Some("json") => ConfigFormat::Json,
+                #[cfg(feature = "toml")]
+                Some("toml") => ConfigFormat::Toml,
+                _ => continue,
             };
-            ruleset_created_ref.add_rule(PathBeneath::new(fd, *allowed_access))?;
-        }
 
-        for (port, allowed_access) in &self.rules_net_port {
-            ruleset_created_ref.add_rule(
-                // TODO: Check integer conversion in parse_json(), which would require changing the type of config and specifying where the error is.
-                NetPort::new((*port).try_into()?, *allowed_access),
-            )?;
+            #[cfg(feature = "toml")]
+            {
+                let stem = path.with_extension("");
+                if let Some(other) = seen_stems.insert(stem.clone(), path.clone()) {
+                    let (json, toml) = if extension == Some("json") {
+                        (path.clone(), other)
+                    } else {
+                        (other, path.clone())
+                    };
+                    return Err(ParseDirectoryError::AmbiguousSource { stem, json, toml });
+                }
+            }
+
+            match format {
+                ConfigFormat::Json => match File::open(&path) {
+                    Ok(file) => match Self::parse_json(file) {
+                        Ok(config) => full_config
+                            .compose_with(&config.with_source(Source::File(path.clone())), mode),
+                        Err(e) => {
+                            errors.insert(path.clone(), e.into());
+                        }
+                    },
+                    Err(e) => {
+                        if e.kind() != std::io::ErrorKind::NotFound {
+                            errors.insert(path.clone(), e.into());
+                        }
+                    }
+                },
+                #[cfg(feature = "toml")]
+                ConfigFormat::Toml => match std::fs::read_to_string(&path) {
+                    Ok(data) => match Self::parse_toml(data.as_str()) {
+                        Ok(config) => full_config
+                            .compose_with(&config.with_source(Source::File(path.clone())), mode),
+                        Err(e) => {
+                            errors.insert(path.clone(), e.into());
+                        }
+                    },
+                    Err(e) => {
+                        if e.kind() != std::io::ErrorKind::NotFound {
+                            errors.insert(path.clone(), e.into());
+                        }
+                    }
+                },
+            }
         }
 
-        Ok((ruleset_created, rule_errors))
+        Ok(())
     }
-}
 
-impl TryFrom<Config> for ResolvedConfig {
-    type Error = ResolveError;
+    /// Discovers and composes a well-known `.landlock.<extension>` file from
+    /// `start` up through every ancestor directory, composing them with
+    /// [`Config::compose`] starting from the outermost ancestor so that a
+    /// file closer to `start` (e.g. a repository-local config) tightens a
+    /// file found higher up (e.g. one in the user's home directory).
+    ///
+    /// Because `compose` is commutative and idempotent (see
+    /// `tests_compose.rs`), this directory-depth order does not change the
+    /// resulting configuration; it only documents the intended precedence
+    /// for readers. Missing files at a given level are silently skipped; an
+    /// existing but invalid file is a hard error. Returns an empty `Config`
+    /// if no file was found at any level.
+    pub fn discover<T>(start: T, format: ConfigFormat) -> Result<Self, ParseFileError>
+    where
+        T: AsRef<Path>,
+    {
+        let start = fs::canonicalize(start)?;
+        Ok(Self::discover_ancestor(&start, &format)?.unwrap_or_else(Self::empty))
+    }
 
-    fn try_from(config: Config) -> Result<Self, Self::Error> {
-        let mut rules_path_beneath: BTreeMap<PathBuf, BitFlags<AccessFs>> = Default::default();
-        for (path_beneath, access) in config.rules_path_beneath {
-            let set = config.variables.resolve(&path_beneath)?;
-            for path in VecStringIterator::new(&set) {
-                rules_path_beneath
-                    .entry(PathBuf::from(path))
-                    .and_modify(|a| *a |= access)
-                    .or_insert(access);
-            }
+    fn discover_ancestor(
+        dir: &Path,
+        format: &ConfigFormat,
+    ) -> Result<Option<Self>, ParseFileError> {
+        let mut config = match dir.parent() {
+            Some(parent) => Self::discover_ancestor(parent, format)?,
+            None => None,
+        };
+
+        let candidate = dir.join(format!(".landlock.{}", format.extension()));
+        if candidate.is_file() {
+            let layer = match format {
+                ConfigFormat::Json => {
+                    Self::parse_json(File::open(&candidate).map_err(ParseFileError::Io)?)?
+                }
+                #[cfg(feature = "toml")]
+                ConfigFormat::Toml => {
+                    Self::parse_toml(&fs::read_to_string(&candidate).map_err(ParseFileError::Io)?)?
+                }
+            };
+            config.compose(&layer);
         }
 
-        Ok(Self {
-            handled_fs: config.handled_fs,
-            handled_net: config.handled_net,
-            scoped: config.scoped,
-            rules_path_beneath,
-            rules_net_port: config.rules_net_port,
-        })
+        Ok(config)
     }
-}
 
-#[cfg(test)]
-mod tests_compose {
-    use super::*;
-    use landlock::{Access, ABI};
+    /// Like [`Config::discover`], but unions each layer's raw [`JsonConfig`]
+    /// with [`crate::parser::JsonConfig::union`] before resolving into a
+    /// [`Config`] just once, instead of composing already-resolved `Config`s
+    /// with [`Config::compose`]'s intersection semantics. A repository-local
+    /// `.landlock.<extension>` can therefore add rules on top of a
+    /// home-directory default without either file's handled access rights
+    /// narrowing the other's, and `abi` ends up as the maximum declared
+    /// across layers. Missing files at a given level are silently skipped;
+    /// an existing but invalid file is a hard error. Returns an empty
+    /// `Config` if no file was found at any level.
+    pub fn discover_merged<T>(start: T, format: ConfigFormat) -> Result<Self, ParseFileError>
+    where
+        T: AsRef<Path>,
+    {
+        let start = fs::canonicalize(start)?;
+        match Self::discover_merged_ancestor(&start, &format)? {
+            Some(json) => Ok(NonEmptyStruct::new(json)
+                .try_into()
+                .map_err(ParseJsonError::from)?),
+            None => Ok(Self::empty()),
+        }
+    }
 
-    #[test]
-    fn test_empty_ruleset() {
-        let mut c1 = Config {
-            handled_fs: AccessFs::Execute.into(),
-            ..Default::default()
+    fn discover_merged_ancestor(
+        dir: &Path,
+        format: &ConfigFormat,
+    ) -> Result<Option<JsonConfig>, ParseFileError> {
+        let mut config = match dir.parent() {
+            Some(parent) => Self::discover_merged_ancestor(parent, format)?,
+            None => None,
         };
-        let c2 = c1.clone();
-        c1.compose(&c2);
-        assert_eq!(c1, c2);
+
+        let candidate = dir.join(format!(".landlock.{}", format.extension()));
+        if candidate.is_file() {
+            let layer: JsonConfig = match format {
+                ConfigFormat::Json => serde_json::from_reader::<_, NonEmptyStruct<JsonConfig>>(
+                    File::open(&candidate).map_err(ParseFileError::Io)?,
+                )
+                .map_err(ParseJsonError::from)?
+                .into_inner(),
+                #[cfg(feature = "toml")]
+                ConfigFormat::Toml => toml::from_str::<NonEmptyStruct<TomlConfig>>(
+                    &fs::read_to_string(&candidate).map_err(ParseFileError::Io)?,
+                )
+                .map_err(ParseTomlError::from)?
+                .into_inner()
+                .into(),
+            };
+            config = Some(match config {
+                Some(base) => base.union(layer),
+                None => layer,
+            });
+        }
+
+        Ok(config)
+    }
+
+    /// Like [`Config::parse_directory`], but also pushes a [`Diagnostic`]
+    /// for every per-file error into `diagnostics`, in addition to returning
+    /// them all bundled in [`ParseDirectoryError::ParseFiles`]. See
+    /// [`Config::parse_json_with_diagnostics`].
+    pub fn parse_directory_with_diagnostics<T>(
+        path: T,
+        format: ConfigFormat,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Self, ParseDirectoryError>
+    where
+        T: AsRef<Path>,
+    {
+        let result = Self::parse_directory(path, format);
+        if let Err(ParseDirectoryError::ParseFiles(errors)) = &result {
+            for (path, error) in errors {
+                let mut diagnostic = Diagnostic::from(error);
+                diagnostic.subject = Some(path.display().to_string());
+                diagnostics.push(diagnostic);
+            }
+        }
+        result
+    }
+
+    /// Parses a JSON configuration file, resolving and merging any `include`
+    /// directive before applying this document's own rules.
+    ///
+    /// Included paths are resolved relative to the including file's
+    /// directory and merged with [`Config::merge`] depth-first, base policy
+    /// first, so a shared base policy can be listed before per-app overlays
+    /// and still lose ties to them. Returns
+    /// [`ParseIncludeError::Cycle`] if a file (transitively) includes
+    /// itself.
+    pub fn parse_json_file<T>(path: T) -> Result<Self, ParseIncludeError>
+    where
+        T: AsRef<Path>,
+    {
+        let mut stack = BTreeSet::new();
+        Self::parse_json_file_inner(path.as_ref(), &mut stack)
+    }
+
+    fn parse_json_file_inner(
+        path: &Path,
+        stack: &mut BTreeSet<PathBuf>,
+    ) -> Result<Self, ParseIncludeError> {
+        let path = fs::canonicalize(path)?;
+        if !stack.insert(path.clone()) {
+            return Err(ParseIncludeError::Cycle(path));
+        }
+
+        let json: NonEmptyStruct<JsonConfig> =
+            serde_json::from_reader(File::open(&path)?).map_err(ParseJsonError::from)?;
+        let json = json.into_inner();
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        let mut config = Self::empty();
+        for include in json.include.clone().unwrap_or_default() {
+            let included = Self::parse_json_file_inner(&dir.join(include), stack)?;
+            config.merge(&included)?;
+        }
+
+        let own: Self = NonEmptyStruct::new(json)
+            .try_into()
+            .map_err(ParseJsonError::from)?;
+        config.merge(&own)?;
+
+        stack.remove(&path);
+        Ok(config)
+    }
+
+    pub fn resolve(self) -> Result<ResolvedConfig, ResolveError> {
+        self.try_into()
+    }
+
+    /// Like [`Config::resolve`], but fails with
+    /// [`ResolveError::TooManyCombinations`] instead of expanding a
+    /// `pathBeneath` template whose `${...}` value sets would produce more
+    /// than `max_combinations` rules. Lets an embedder resolving untrusted
+    /// configs bound memory and kernel rule counts instead of trusting
+    /// whoever wrote the config not to reference e.g. three ten-value
+    /// variables in the same path.
+    pub fn resolve_with_limits(
+        self,
+        max_combinations: u64,
+    ) -> Result<ResolvedConfig, ResolveError> {
+        resolve_config(self, max_combinations, VariableSource::ConfigOnly)
+    }
+
+    /// Like [`Config::resolve`], but accepts `var_source` to opt into
+    /// resolving an undeclared `${name}` from the allowlisted
+    /// `LANDLOCKCONFIG_<NAME>` environment variable instead of failing,
+    /// e.g. `config.resolve_with_source(VariableSource::ConfigAndEnv)` to
+    /// let deployment-specific paths like `${HOME}` come from the
+    /// environment without being hardcoded into the document.
+    pub fn resolve_with_source(
+        self,
+        var_source: VariableSource,
+    ) -> Result<ResolvedConfig, ResolveError> {
+        resolve_config(self, u64::MAX, var_source)
+    }
+
+    /// Best-effort downgrade of this configuration to an older Landlock ABI.
+    ///
+    /// Intersects every handled access set with the rights available under
+    /// `target` (e.g. `AccessFs::from_all(target)`), drops any
+    /// `pathBeneath`/`netPort` rule whose access becomes empty as a result,
+    /// and resolves what remains. Unlike [`Config::resolve`], this never
+    /// fails because of version mismatches: every right and rule that had to
+    /// be removed is instead recorded in the returned [`AbiDowngradeReport`],
+    /// so callers can implement Landlock's "best effort" posture while
+    /// knowing exactly what weakened.
+    pub fn resolve_for_abi(
+        mut self,
+        target: ABI,
+    ) -> Result<(ResolvedConfig, AbiDowngradeReport), ResolveError> {
+        let report = self.downgrade_to_abi(target);
+        Ok((self.resolve()?, report))
+    }
+
+    /// Intersects every handled access set and rule with the rights
+    /// available under `target`, dropping any `pathBeneath`/`netPort` rule
+    /// whose access becomes empty as a result, and recording everything
+    /// removed in the returned [`AbiDowngradeReport`]. Shared by
+    /// [`Config::resolve_for_abi`] and the implicit clamp a parsed `{ min,
+    /// max }` [`AbiRequirement::Range`] applies against its `min`.
+    fn downgrade_to_abi(&mut self, target: ABI) -> AbiDowngradeReport {
+        let target_fs = AccessFs::from_all(target);
+        let target_net = AccessNet::from_all(target);
+        let target_scope = Scope::from_all(target);
+
+        let mut report = AbiDowngradeReport { dropped: vec![] };
+
+        for right in (self.handled_fs & !target_fs).iter() {
+            report.dropped.push(DroppedRight {
+                description: format!("handled filesystem access right `{right:?}`"),
+                minimum_abi: minimum_abi_fs(right),
+            });
+        }
+        for right in (self.handled_net & !target_net).iter() {
+            report.dropped.push(DroppedRight {
+                description: format!("handled network access right `{right:?}`"),
+                minimum_abi: minimum_abi_net(right),
+            });
+        }
+        for right in (self.scoped & !target_scope).iter() {
+            report.dropped.push(DroppedRight {
+                description: format!("scope `{right:?}`"),
+                minimum_abi: minimum_abi_scope(right),
+            });
+        }
+
+        self.handled_fs &= target_fs;
+        self.handled_net &= target_net;
+        self.scoped &= target_scope;
+
+        self.rules_path_beneath.retain(|parent, access| {
+            let original = *access;
+            *access &= target_fs;
+            if access.is_empty() {
+                report.dropped.push(DroppedRight {
+                    description: format!("pathBeneath rule for `{parent}`"),
+                    minimum_abi: max_minimum_abi(original, minimum_abi_fs),
+                });
+                false
+            } else {
+                true
+            }
+        });
+
+        self.rules_net_port.retain(|port, access| {
+            let original = *access;
+            *access &= target_net;
+            if access.is_empty() {
+                let port_desc = if port.start == port.end {
+                    port.start.to_string()
+                } else {
+                    format!("{}-{}", port.start, port.end)
+                };
+                report.dropped.push(DroppedRight {
+                    description: format!("netPort rule for `{port_desc}`"),
+                    minimum_abi: max_minimum_abi(original, minimum_abi_net),
+                });
+                false
+            } else {
+                true
+            }
+        });
+
+        report
+    }
+
+    /// Checks whether this configuration would behave identically on an
+    /// older kernel, without downgrading or applying anything.
+    ///
+    /// Resolves the configuration and delegates to
+    /// [`ResolvedConfig::compatibility_report`] against `target`. Pass e.g.
+    /// `ABI::V3` to confirm a policy authored against the latest ABI will
+    /// still behave as intended once deployed to an older kernel, instead
+    /// of only discovering the gap via [`Config::resolve_for_abi`]'s
+    /// best-effort downgrade at runtime.
+    pub fn validate_for_abi(&self, target: ABI) -> Result<CompatibilityReport, ResolveError> {
+        Ok(self.clone().resolve()?.compatibility_report(target))
+    }
+
+    /// Resolves this configuration and builds its ruleset in one call,
+    /// assuming every handled access is supported by the running kernel.
+    /// See [`Config::build_ruleset_with_compat`] for ABI negotiation instead.
+    pub fn build_ruleset(&self) -> Result<(RulesetCreated, Vec<RuleError>), BuildRulesetError> {
+        self.clone().resolve()?.build_ruleset()
+    }
+
+    /// Like [`Config::build_ruleset_with_compat`], but negotiates using this
+    /// configuration's own parsed `compatibility` field (`"best_effort"`,
+    /// `"soft_requirement"`, or `"hard_requirement"`, defaulting to
+    /// [`CompatLevel::BestEffort`] when absent) instead of requiring the
+    /// caller to pass one. This is the effect `compatibility` actually has
+    /// once the configuration is turned into a ruleset.
+    pub fn build_ruleset_with_configured_compat(
+        &self,
+    ) -> Result<(RulesetCreated, Vec<RuleError>, AbiDowngradeReport), BuildRulesetError> {
+        self.build_ruleset_with_compat(self.compatibility)
+    }
+
+    /// Like [`Config::build_ruleset`], but first negotiates the
+    /// handled access sets against [`detected_abi`] per `level` instead of
+    /// assuming every handled access in this configuration is supported by
+    /// the running kernel.
+    ///
+    /// Returns the [`AbiDowngradeReport`] of whatever had to be dropped to
+    /// fit the detected ABI, empty in [`CompatLevel::HardRequirement`] mode
+    /// since that mode errors instead of dropping anything, so callers can
+    /// log the effective restriction instead of assuming the config applied
+    /// exactly as written.
+    pub fn build_ruleset_with_compat(
+        &self,
+        level: CompatLevel,
+    ) -> Result<(RulesetCreated, Vec<RuleError>, AbiDowngradeReport), BuildRulesetError> {
+        self.build_ruleset_for_abi(detected_abi(), level)
+    }
+
+    /// Core of [`Config::build_ruleset_with_compat`], parameterized on the
+    /// target ABI instead of always querying [`detected_abi`], so the
+    /// negotiation logic can be tested without depending on the Landlock
+    /// version supported by the machine running the tests.
+    fn build_ruleset_for_abi(
+        &self,
+        target: ABI,
+        level: CompatLevel,
+    ) -> Result<(RulesetCreated, Vec<RuleError>, AbiDowngradeReport), BuildRulesetError> {
+        match level {
+            CompatLevel::HardRequirement => {
+                let report = self.validate_for_abi(target)?;
+                if let Some(dropped) = report.unsupported.dropped.into_iter().next() {
+                    return Err(BuildRulesetError::Unsupported {
+                        description: dropped.description,
+                        minimum_abi: dropped.minimum_abi,
+                        detected: target,
+                    });
+                }
+                let (ruleset, rule_errors) = self.build_ruleset()?;
+                Ok((ruleset, rule_errors, AbiDowngradeReport::default()))
+            }
+            CompatLevel::SoftRequirement => {
+                let handled_before_downgrade = !self.handled_fs.is_empty()
+                    || !self.handled_net.is_empty()
+                    || !self.scoped.is_empty();
+                let (resolved, report) = self.clone().resolve_for_abi(target)?;
+                if handled_before_downgrade
+                    && resolved.handled_fs.is_empty()
+                    && resolved.handled_net.is_empty()
+                    && resolved.scoped.is_empty()
+                {
+                    return Err(BuildRulesetError::Unsupported {
+                        description: "every handled access right".to_string(),
+                        minimum_abi: max_minimum_abi(self.handled_fs, minimum_abi_fs),
+                        detected: target,
+                    });
+                }
+                let (ruleset, rule_errors) = resolved.build_ruleset()?;
+                Ok((ruleset, rule_errors, report))
+            }
+            CompatLevel::BestEffort => {
+                let (resolved, report) = self.clone().resolve_for_abi(target)?;
+                let (ruleset, rule_errors) = resolved.build_ruleset()?;
+                Ok((ruleset, rule_errors, report))
+            }
+        }
+    }
+
+    /// Checks this configuration for semantic issues that don't prevent it
+    /// from being built but make it ineffective, e.g. a `pathBeneath`/
+    /// `netPort` rule allowing an access right the ruleset never handles, or
+    /// a ruleset that handles nothing at all. Never touches the filesystem;
+    /// see [`Config::validate_with_paths`] for that.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (path, access) in &self.rules_path_beneath {
+            let unhandled = *access & !self.handled_fs;
+            if !unhandled.is_empty() {
+                errors.push(ValidationError::UnhandledPathAccess {
+                    path: path.to_string(),
+                    access: unhandled,
+                });
+            }
+        }
+
+        for (range, access) in &self.rules_net_port {
+            let unhandled = *access & !self.handled_net;
+            if !unhandled.is_empty() {
+                let port = if range.start == range.end {
+                    range.start.to_string()
+                } else {
+                    format!("{}-{}", range.start, range.end)
+                };
+                errors.push(ValidationError::UnhandledNetAccess {
+                    port,
+                    access: unhandled,
+                });
+            }
+        }
+
+        if self.handled_fs.is_empty() && self.handled_net.is_empty() && self.scoped.is_empty() {
+            errors.push(ValidationError::EmptyRuleset);
+        }
+
+        errors
+    }
+
+    /// Like [`Config::validate`], but additionally resolves this
+    /// configuration and checks that every `pathBeneath` parent exists and
+    /// can be opened, via [`ResolvedConfig::check`], and that no rule allows
+    /// a directory-only access right (`read_dir`, `make_reg`, ...) against a
+    /// parent that resolves to a regular file. Opt-in and behind its own
+    /// method, rather than a flag on [`Config::validate`], so pure config
+    /// linting never has to touch the filesystem.
+    pub fn validate_with_paths(&self) -> Result<Vec<ValidationError>, ResolveError> {
+        let mut errors = self.validate();
+        let resolved = self.clone().resolve()?;
+
+        for issue in resolved.check().path_issues {
+            errors.push(ValidationError::PathNotFound {
+                path: issue.path,
+                description: issue.description,
+            });
+        }
+
+        // Rights that only make sense against a directory: allowing one of
+        // these on a parent that's actually a regular file means it can
+        // never be exercised. `landlock` itself doesn't expose this split
+        // (the kernel enforces it), so it's reconstructed here from the
+        // `AccessFs` variants that create/remove/list directory entries.
+        let directory_only = AccessFs::ReadDir
+            | AccessFs::RemoveDir
+            | AccessFs::MakeChar
+            | AccessFs::MakeDir
+            | AccessFs::MakeReg
+            | AccessFs::MakeSock
+            | AccessFs::MakeFifo
+            | AccessFs::MakeBlock
+            | AccessFs::MakeSym;
+
+        for (path, access) in &resolved.rules_path_beneath {
+            let offending = *access & directory_only;
+            if offending.is_empty() {
+                continue;
+            }
+            if fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) {
+                errors.push(ValidationError::DirectoryOnlyAccessOnFile {
+                    path: path.clone(),
+                    access: offending,
+                });
+            }
+        }
+
+        Ok(errors)
+    }
+
+    fn to_json_config(&self, fold_abi: Option<ABI>) -> JsonConfig {
+        let variable = {
+            let items: BTreeSet<JsonVariable> = self
+                .variables
+                .iter()
+                .map(|(name, literal)| JsonVariable {
+                    name: name.to_string(),
+                    literal: Some(literal.iter().cloned().collect()),
+                })
+                .collect();
+            (!items.is_empty()).then(|| items.into_iter().collect())
+        };
+
+        let path_beneath = self
+            .rules_path_beneath
+            .iter()
+            .map(|(path, access)| (path.to_string(), *access));
+        let net_port = self
+            .rules_net_port
+            .iter()
+            .map(|(port, access)| (*port, *access));
+
+        // Only emit `compatibility` when it differs from the implicit
+        // default, so a plain config round-trips to the same minimal form.
+        let compatibility = (self.compatibility != CompatLevel::default())
+            .then_some(JsonCompatLevel::from(self.compatibility));
+
+        build_json_config(
+            variable,
+            self.handled_fs,
+            self.handled_net,
+            self.scoped,
+            path_beneath,
+            net_port,
+            fold_abi,
+            compatibility,
+        )
+    }
+
+    /// Serializes this configuration back into the same JSON schema accepted
+    /// by [`Config::parse_json`], expanding any `abi.*`/`vN.*` access groups
+    /// into their concrete access rights.
+    pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.to_json_config(None))
+    }
+
+    /// Like [`Config::to_json_string`], but writes directly to `writer`
+    /// instead of building an intermediate `String`.
+    pub fn to_json_writer<W>(&self, writer: W) -> Result<(), serde_json::Error>
+    where
+        W: std::io::Write,
+    {
+        serde_json::to_writer_pretty(writer, &self.to_json_config(None))
+    }
+
+    /// Like [`Config::to_json_string`], but first tries to fold each access
+    /// set back into the `abi.all`/`abi.read_execute`/`abi.read_write` alias
+    /// it exactly matches under `abi`, for a more concise (but `abi`-scoped)
+    /// rendering.
+    pub fn to_json_string_for_abi(&self, abi: ABI) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.to_json_config(Some(abi)))
+    }
+
+    /// Serializes this configuration back into the same TOML schema accepted
+    /// by [`Config::parse_toml`].
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(&TomlConfig::from(self.to_json_config(None)))
+    }
+
+    /// TOML counterpart of [`Config::to_json_string_for_abi`].
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string_for_abi(&self, abi: ABI) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(&TomlConfig::from(self.to_json_config(Some(abi))))
+    }
+}
+
+/// Groups path and port rules by their (decomposed) access sets and
+/// assembles a [`JsonConfig`] ready for serialization.
+///
+/// When `fold_abi` is set, each access set is first folded back into the
+/// `abi.*` alias it exactly matches under that ABI, falling back to the
+/// concrete decomposition otherwise; when it's `None`, access sets are
+/// always expanded into concrete rights, so the output is unambiguous
+/// regardless of which ABI produced it.
+fn build_json_config(
+    variable: Option<BTreeSet<JsonVariable>>,
+    handled_fs: BitFlags<AccessFs>,
+    handled_net: BitFlags<AccessNet>,
+    scoped: BitFlags<Scope>,
+    path_beneath: impl Iterator<Item = (String, BitFlags<AccessFs>)>,
+    net_port: impl Iterator<Item = (PortRange, BitFlags<AccessNet>)>,
+    fold_abi: Option<ABI>,
+    compatibility: Option<JsonCompatLevel>,
+) -> JsonConfig {
+    let fs_items = |access| match fold_abi {
+        Some(abi) => fold_access_fs_items(access, abi),
+        None => access_fs_items(access),
+    };
+    let net_items = |access| match fold_abi {
+        Some(abi) => fold_access_net_items(access, abi),
+        None => access_net_items(access),
+    };
+
+    let handled_access_fs = fs_items(handled_fs);
+    let handled_access_net = net_items(handled_net);
+    let scoped = match fold_abi {
+        Some(abi) => fold_scope_items(scoped, abi),
+        None => scope_items(scoped),
+    };
+    let ruleset =
+        (!handled_access_fs.is_empty() || !handled_access_net.is_empty() || !scoped.is_empty())
+            .then(|| {
+                let inner = JsonRuleset {
+                    handledAccessFs: (!handled_access_fs.is_empty()).then(|| {
+                        handled_access_fs
+                            .into_iter()
+                            .map(JsonFsAccessEntry::Item)
+                            .collect()
+                    }),
+                    handledAccessNet: (!handled_access_net.is_empty()).then(|| {
+                        handled_access_net
+                            .into_iter()
+                            .map(JsonNetAccessEntry::Item)
+                            .collect()
+                    }),
+                    scoped: (!scoped.is_empty())
+                        .then(|| scoped.into_iter().map(JsonScopeEntry::Item).collect()),
+                };
+                [NonEmptyStruct::new(inner)].into_iter().collect()
+            });
+
+    let mut path_groups: BTreeMap<BTreeSet<_>, BTreeSet<String>> = BTreeMap::new();
+    for (path, access) in path_beneath {
+        path_groups
+            .entry(fs_items(access))
+            .or_default()
+            .insert(path);
+    }
+    let path_beneath = (!path_groups.is_empty()).then(|| {
+        path_groups
+            .into_iter()
+            .map(|(access, paths)| JsonPathBeneath {
+                allowedAccess: access.into_iter().map(JsonFsAccessEntry::Item).collect(),
+                parent: paths
+                    .into_iter()
+                    .map(|p| TemplateString(vec![TemplateToken::Text(p)]))
+                    .collect(),
+            })
+            .collect()
+    });
+
+    let mut port_groups: BTreeMap<BTreeSet<_>, BTreeSet<PortRange>> = BTreeMap::new();
+    for (port, access) in net_port {
+        port_groups
+            .entry(net_items(access))
+            .or_default()
+            .insert(port);
+    }
+    let net_port = (!port_groups.is_empty()).then(|| {
+        port_groups
+            .into_iter()
+            .map(|(access, ports)| JsonNetPort {
+                allowedAccess: access.into_iter().map(JsonNetAccessEntry::Item).collect(),
+                port: ports.into_iter().collect(),
+            })
+            .collect()
+    });
+
+    JsonConfig {
+        abi: None,
+        compatibility,
+        include: None,
+        variable,
+        ruleset,
+        pathBeneath: path_beneath,
+        netPort: net_port,
+        profiles: None,
+    }
+}
+
+#[test]
+fn test_config_default_empty() {
+    let config = Config::default();
+    assert_eq!(config.handled_fs, BitFlags::EMPTY);
+    assert_eq!(config.handled_net, BitFlags::EMPTY);
+    assert_eq!(config.scoped, BitFlags::EMPTY);
+
+    assert_eq!(config, Config::empty());
+}
+
+pub trait OptionalConfig {
+    fn compose(&mut self, other: &Config);
+    fn compose_with(&mut self, other: &Config, mode: ComposeMode);
+}
+
+impl OptionalConfig for Option<Config> {
+    fn compose(&mut self, other: &Config) {
+        match self {
+            Some(config) => config.compose(other),
+            None => *self = Some(other.clone()),
+        }
+    }
+
+    fn compose_with(&mut self, other: &Config, mode: ComposeMode) {
+        match self {
+            Some(config) => config.compose_with(other, mode),
+            None => *self = Some(other.clone()),
+        }
+    }
+}
+
+/// Best Landlock ABI supported by the running kernel.
+pub fn detected_abi() -> ABI {
+    ABI::new_current()
+}
+
+/// Report produced by [`ResolvedConfig::compatibility_report`], comparing a
+/// configuration against a target Landlock ABI (typically [`detected_abi`]).
+#[cfg_attr(test, derive(Default))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// Access rights and scopes the configuration handles that `abi` cannot
+    /// enforce.
+    pub unsupported: AbiDowngradeReport,
+    /// Filesystem access rights `abi` supports that the configuration never
+    /// handles, i.e. rights the policy could additionally restrict.
+    pub unused_fs: BitFlags<AccessFs>,
+    /// Network access rights `abi` supports that the configuration never
+    /// handles.
+    pub unused_net: BitFlags<AccessNet>,
+    /// Scopes `abi` supports that the configuration never handles.
+    pub unused_scoped: BitFlags<Scope>,
+}
+
+impl CompatibilityReport {
+    /// Whether `abi` supports every right the config handles and the config
+    /// handles every right `abi` supports.
+    pub fn is_fully_compatible(&self) -> bool {
+        self.unsupported.is_empty()
+            && self.unused_fs.is_empty()
+            && self.unused_net.is_empty()
+            && self.unused_scoped.is_empty()
+    }
+}
+
+/// Report produced by [`ResolvedConfig::negotiate_abi`], recording the
+/// target ABI that was negotiated against, everything that had to be
+/// dropped to fit it, and the [`CompatLevel`] that governed the
+/// negotiation, so a caller can log exactly what a best-effort deployment
+/// actually ended up enforcing instead of only inferring it from
+/// composition's intersection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbiCompatReport {
+    pub negotiated_abi: ABI,
+    pub dropped: AbiDowngradeReport,
+    pub compat: CompatLevel,
+}
+
+/// A single `pathBeneath` parent that does not exist or could not be
+/// opened, found by [`ResolvedConfig::check`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathCheckIssue {
+    pub path: PathBuf,
+    pub description: String,
+}
+
+/// Report produced by [`ResolvedConfig::check`], listing every
+/// `pathBeneath` parent that failed the dry-run existence check.
+#[cfg_attr(test, derive(Default))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckReport {
+    pub path_issues: Vec<PathCheckIssue>,
+}
+
+impl CheckReport {
+    pub fn is_empty(&self) -> bool {
+        self.path_issues.is_empty()
+    }
+}
+
+impl ResolvedConfig {
+    /// Sources tagged via [`Config::with_source`] that contributed a
+    /// `pathBeneath` rule for this resolved (post-variable-expansion)
+    /// `path`; empty if provenance was never tracked or no source
+    /// contributed a rule for it.
+    pub fn origins_for_path(&self, path: &Path) -> &[Source] {
+        self.provenance
+            .rules_path_beneath
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Sources tagged via [`Config::with_source`] that set `access` in
+    /// `handledAccessFs`.
+    pub fn origins_for_access(&self, access: AccessFs) -> &[Source] {
+        origins_for_fs(&self.provenance.handled_fs, access)
+    }
+
+    /// Sources tagged via [`Config::with_source`] that set `access` in
+    /// `handledAccessNet`.
+    pub fn origins_for_net_access(&self, access: AccessNet) -> &[Source] {
+        origins_for_net(&self.provenance.handled_net, access)
+    }
+
+    /// Sources tagged via [`Config::with_source`] that set `scope` in
+    /// `scoped`.
+    pub fn origins_for_scope(&self, scope: Scope) -> &[Source] {
+        origins_for_scope(&self.provenance.scoped, scope)
+    }
+
+    /// Verifies that every `pathBeneath` parent exists and can be opened,
+    /// without building or applying a ruleset.
+    ///
+    /// Unlike [`ResolvedConfig::build_ruleset`], which skips the rule of an
+    /// unopenable path (returning it as a [`RuleError`]) while still
+    /// creating a ruleset, this is purely a dry-run: every unopenable path
+    /// is recorded in the returned [`CheckReport`] and nothing is created.
+    pub fn check(&self) -> CheckReport {
+        let path_issues = self
+            .rules_path_beneath
+            .keys()
+            .filter_map(|path| match PathFd::new(path) {
+                Ok(_) => None,
+                Err(e) => Some(PathCheckIssue {
+                    path: path.clone(),
+                    description: e.to_string(),
+                }),
+            })
+            .collect();
+        CheckReport { path_issues }
+    }
+
+    /// Compares this configuration against `abi`, reporting rights it
+    /// handles that `abi` cannot enforce and rights `abi` supports that it
+    /// never handles, the latter being a hint that the policy could be
+    /// tightened. Pass [`detected_abi`] to compare against the running
+    /// kernel.
+    pub fn compatibility_report(&self, abi: ABI) -> CompatibilityReport {
+        let target_fs = AccessFs::from_all(abi);
+        let target_net = AccessNet::from_all(abi);
+        let target_scope = Scope::from_all(abi);
+
+        let mut dropped = vec![];
+        for right in (self.handled_fs & !target_fs).iter() {
+            dropped.push(DroppedRight {
+                description: format!("handled filesystem access right `{right:?}`"),
+                minimum_abi: minimum_abi_fs(right),
+            });
+        }
+        for right in (self.handled_net & !target_net).iter() {
+            dropped.push(DroppedRight {
+                description: format!("handled network access right `{right:?}`"),
+                minimum_abi: minimum_abi_net(right),
+            });
+        }
+        for right in (self.scoped & !target_scope).iter() {
+            dropped.push(DroppedRight {
+                description: format!("scope `{right:?}`"),
+                minimum_abi: minimum_abi_scope(right),
+            });
+        }
+
+        CompatibilityReport {
+            unsupported: AbiDowngradeReport { dropped },
+            unused_fs: target_fs & !self.handled_fs,
+            unused_net: target_net & !self.handled_net,
+            unused_scoped: target_scope & !self.scoped,
+        }
+    }
+
+    /// Maps this configuration's handled rights onto `abi` per `compat`,
+    /// returning a new, downgraded [`ResolvedConfig`] alongside a
+    /// structured [`AbiCompatReport`], instead of only ever inferring the
+    /// effective access set as the intersection [`Config::compose`]
+    /// computes across fragments.
+    ///
+    /// - [`CompatLevel::HardRequirement`]: fails with
+    ///   [`BuildRulesetError::Unsupported`] if `abi` can't enforce
+    ///   everything this configuration handles; nothing is dropped.
+    /// - [`CompatLevel::SoftRequirement`]: downgrades silently unless doing
+    ///   so would drop every handled right, in which case it fails the
+    ///   same way as [`CompatLevel::HardRequirement`].
+    /// - [`CompatLevel::BestEffort`]: always downgrades silently, even down
+    ///   to an empty ruleset.
+    ///
+    /// Mirrors the negotiation [`Config::build_ruleset_with_compat`]
+    /// performs right before building a kernel ruleset, but stops short of
+    /// that: useful for a caller that wants to inspect or log what a
+    /// target ABI would cost before committing to it.
+    pub fn negotiate_abi(
+        &self,
+        abi: ABI,
+        compat: CompatLevel,
+    ) -> Result<(ResolvedConfig, AbiCompatReport), BuildRulesetError> {
+        match compat {
+            CompatLevel::HardRequirement => {
+                let report = self.compatibility_report(abi);
+                if let Some(dropped) = report.unsupported.dropped.into_iter().next() {
+                    return Err(BuildRulesetError::Unsupported {
+                        description: dropped.description,
+                        minimum_abi: dropped.minimum_abi,
+                        detected: abi,
+                    });
+                }
+                Ok((
+                    self.clone(),
+                    AbiCompatReport {
+                        negotiated_abi: abi,
+                        dropped: AbiDowngradeReport::default(),
+                        compat,
+                    },
+                ))
+            }
+            CompatLevel::SoftRequirement => {
+                let handled_before = !self.handled_fs.is_empty()
+                    || !self.handled_net.is_empty()
+                    || !self.scoped.is_empty();
+                let mut downgraded = self.clone();
+                let dropped = downgraded.downgrade_to_abi(abi);
+                if handled_before
+                    && downgraded.handled_fs.is_empty()
+                    && downgraded.handled_net.is_empty()
+                    && downgraded.scoped.is_empty()
+                {
+                    return Err(BuildRulesetError::Unsupported {
+                        description: "every handled access right".to_string(),
+                        minimum_abi: max_minimum_abi(self.handled_fs, minimum_abi_fs),
+                        detected: abi,
+                    });
+                }
+                Ok((
+                    downgraded,
+                    AbiCompatReport {
+                        negotiated_abi: abi,
+                        dropped,
+                        compat,
+                    },
+                ))
+            }
+            CompatLevel::BestEffort => {
+                let mut downgraded = self.clone();
+                let dropped = downgraded.downgrade_to_abi(abi);
+                Ok((
+                    downgraded,
+                    AbiCompatReport {
+                        negotiated_abi: abi,
+                        dropped,
+                        compat,
+                    },
+                ))
+            }
+        }
+    }
+
+    /// [`ResolvedConfig`] counterpart of `Config`'s own private downgrade
+    /// helper: same intersect-and-record downgrade, just against the
+    /// already-resolved, already-normalized `rules_path_beneath`/
+    /// `rules_net_port` keys (`PathBuf`/`u64`) instead of
+    /// `TemplateString`/`PortRange`.
+    fn downgrade_to_abi(&mut self, target: ABI) -> AbiDowngradeReport {
+        let target_fs = AccessFs::from_all(target);
+        let target_net = AccessNet::from_all(target);
+        let target_scope = Scope::from_all(target);
+
+        let mut report = AbiDowngradeReport { dropped: vec![] };
+
+        for right in (self.handled_fs & !target_fs).iter() {
+            report.dropped.push(DroppedRight {
+                description: format!("handled filesystem access right `{right:?}`"),
+                minimum_abi: minimum_abi_fs(right),
+            });
+        }
+        for right in (self.handled_net & !target_net).iter() {
+            report.dropped.push(DroppedRight {
+                description: format!("handled network access right `{right:?}`"),
+                minimum_abi: minimum_abi_net(right),
+            });
+        }
+        for right in (self.scoped & !target_scope).iter() {
+            report.dropped.push(DroppedRight {
+                description: format!("scope `{right:?}`"),
+                minimum_abi: minimum_abi_scope(right),
+            });
+        }
+
+        self.handled_fs &= target_fs;
+        self.handled_net &= target_net;
+        self.scoped &= target_scope;
+
+        self.rules_path_beneath.retain(|path, access| {
+            let original = *access;
+            *access &= target_fs;
+            if access.is_empty() {
+                report.dropped.push(DroppedRight {
+                    description: format!("pathBeneath rule for `{}`", path.display()),
+                    minimum_abi: max_minimum_abi(original, minimum_abi_fs),
+                });
+                false
+            } else {
+                true
+            }
+        });
+
+        self.rules_net_port.retain(|port, access| {
+            let original = *access;
+            *access &= target_net;
+            if access.is_empty() {
+                report.dropped.push(DroppedRight {
+                    description: format!("netPort rule for `{port}`"),
+                    minimum_abi: max_minimum_abi(original, minimum_abi_net),
+                });
+                false
+            } else {
+                true
+            }
+        });
+
+        report
+    }
+
+    pub fn build_ruleset(&self) -> Result<(RulesetCreated, Vec<RuleError>), BuildRulesetError> {
+        let mut ruleset = Ruleset::default();
+        let ruleset_ref = &mut ruleset;
+        if !self.handled_fs.is_empty() {
+            ruleset_ref.handle_access(self.handled_fs)?;
+        }
+        if !self.handled_net.is_empty() {
+            ruleset_ref.handle_access(self.handled_net)?;
+        }
+        if !self.scoped.is_empty() {
+            ruleset_ref.scope(self.scoped)?;
+        }
+        let mut ruleset_created = ruleset.create()?;
+        let ruleset_created_ref = &mut ruleset_created;
+        let mut rule_errors = Vec::new();
+
+        for (parent, allowed_access) in &self.rules_path_beneath {
+            // TODO: Walk through all path and only open them once, including their
+            // common parent directory to get a consistent hierarchy.
+            let fd = match PathFd::new(parent) {
+                Ok(fd) => fd,
+                Err(e) => {
+                    rule_errors.push(RuleError::PathFd {
+                        path: parent.clone(),
+                        source: e,
+                    });
+                    continue;
+                }
+            };
+            ruleset_created_ref.add_rule(PathBeneath::new(fd, *allowed_access))?;
+        }
+
+        for (port, allowed_access) in &self.rules_net_port {
+            ruleset_created_ref.add_rule(
+                // TODO: Check integer conversion in parse_json(), which would require changing the type of config and specifying where the error is.
+                NetPort::new((*port).try_into()?, *allowed_access),
+            )?;
+        }
+
+        Ok((ruleset_created, rule_errors))
+    }
+
+    /// Like [`ResolvedConfig::build_ruleset`], but reports every dropped
+    /// [`RuleError`] into `diagnostics` as a warning instead of returning
+    /// them in a separate `Vec`, and reports a hard failure as an error
+    /// entry too, for callers (e.g. across the C FFI) that surface
+    /// structured diagnostics instead of only a `Result`.
+    pub fn build_ruleset_with_diagnostics(
+        &self,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<RulesetCreated, BuildRulesetError> {
+        match self.build_ruleset() {
+            Ok((ruleset, rule_errors)) => {
+                for error in &rule_errors {
+                    diagnostics.push(error.into());
+                }
+                Ok(ruleset)
+            }
+            Err(e) => {
+                diagnostics.push((&e).into());
+                Err(e)
+            }
+        }
+    }
+
+    fn to_json_config(&self, fold_abi: Option<ABI>) -> JsonConfig {
+        let path_beneath = self
+            .rules_path_beneath
+            .iter()
+            .map(|(path, access)| (path.display().to_string(), *access));
+        let net_port = self
+            .rules_net_port
+            .iter()
+            .map(|(port, access)| (PortRange::single(*port), *access));
+
+        build_json_config(
+            None,
+            self.handled_fs,
+            self.handled_net,
+            self.scoped,
+            path_beneath,
+            net_port,
+            fold_abi,
+            None,
+        )
+    }
+
+    /// Serializes this resolved configuration back into the JSON schema
+    /// accepted by [`Config::parse_json`], with all variables already
+    /// expanded into literal paths.
+    pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.to_json_config(None))
+    }
+
+    /// Like [`ResolvedConfig::to_json_string`], but folds access sets back
+    /// into `abi.*` aliases. See [`Config::to_json_string_for_abi`].
+    pub fn to_json_string_for_abi(&self, abi: ABI) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.to_json_config(Some(abi)))
+    }
+
+    /// Serializes this resolved configuration back into the TOML schema
+    /// accepted by [`Config::parse_toml`], with all variables already
+    /// expanded into literal paths.
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(&TomlConfig::from(self.to_json_config(None)))
+    }
+
+    /// TOML counterpart of [`ResolvedConfig::to_json_string_for_abi`].
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string_for_abi(&self, abi: ABI) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(&TomlConfig::from(self.to_json_config(Some(abi))))
+    }
+}
+
+impl TryFrom<Config> for ResolvedConfig {
+    type Error = ResolveError;
+
+    fn try_from(config: Config) -> Result<Self, Self::Error> {
+        resolve_config(config, u64::MAX, VariableSource::ConfigOnly)
+    }
+}
+
+/// Shared by [`Config::resolve`] (via its [`TryFrom`] impl),
+/// [`Config::resolve_with_limits`], and [`Config::resolve_with_source`]:
+/// `max_combinations` bounds the Cartesian product
+/// [`Variables::resolve_with_limit_and_source`] allows for any single
+/// `pathBeneath` template, with [`Config::resolve`] passing [`u64::MAX`] to
+/// keep its existing, unbounded behavior; `var_source` selects whether an
+/// undeclared `${name}` may fall back to the environment.
+fn resolve_config(
+    mut config: Config,
+    max_combinations: u64,
+    var_source: VariableSource,
+) -> Result<ResolvedConfig, ResolveError> {
+    let mut rules_path_beneath: BTreeMap<PathBuf, BitFlags<AccessFs>> = Default::default();
+    let mut path_provenance: BTreeMap<PathBuf, Vec<Source>> = Default::default();
+    for (path_beneath, access) in config.rules_path_beneath {
+        let set = config.variables.resolve_with_limit_and_source(
+            &path_beneath,
+            max_combinations,
+            var_source,
+        )?;
+        let sources = config.provenance.rules_path_beneath.remove(&path_beneath);
+        for path in VecStringIterator::new(&set) {
+            // Different combinations resolved from the Cartesian product
+            // can concatenate to the same path once cleaned (e.g. `a//b`
+            // and `a/b`), so the key is the normalized path: dedup must
+            // be global across all combinations, not just consecutive
+            // ones, since normalizing can reorder which compare equal.
+            let normalized = lexically_normalize(&path);
+            rules_path_beneath
+                .entry(normalized.clone())
+                .and_modify(|a| *a |= access)
+                .or_insert(access);
+            if let Some(sources) = &sources {
+                let entry = path_provenance.entry(normalized).or_default();
+                for source in sources {
+                    add_source(entry, source);
+                }
+            }
+        }
+    }
+
+    let mut rules_net_port: BTreeMap<u64, BitFlags<AccessNet>> = Default::default();
+    let mut port_provenance: BTreeMap<u64, Vec<Source>> = Default::default();
+    for (range, access) in config.rules_net_port {
+        let sources = config.provenance.rules_net_port.remove(&range);
+        for port in range.iter() {
+            rules_net_port
+                .entry(port)
+                .and_modify(|a| *a |= access)
+                .or_insert(access);
+            if let Some(sources) = &sources {
+                let entry = port_provenance.entry(port).or_default();
+                for source in sources {
+                    add_source(entry, source);
+                }
+            }
+        }
+    }
+
+    Ok(ResolvedConfig {
+        handled_fs: config.handled_fs,
+        handled_net: config.handled_net,
+        scoped: config.scoped,
+        rules_path_beneath,
+        rules_net_port,
+        provenance: ResolvedProvenance {
+            handled_fs: config.provenance.handled_fs,
+            handled_net: config.provenance.handled_net,
+            scoped: config.provenance.scoped,
+            rules_path_beneath: path_provenance,
+            rules_net_port: port_provenance,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests_compose {
+    use super::*;
+    use landlock::{Access, ABI};
+
+    #[test]
+    fn test_empty_ruleset() {
+        let mut c1 = Config {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        };
+        let c2 = c1.clone();
+        c1.compose(&c2);
+        assert_eq!(c1, c2);
+    }
+
+    #[test]
+    fn test_different_ruleset() {
+        let mut c1 = Config {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        };
+        let c2 = Config {
+            handled_net: AccessNet::BindTcp.into(),
+            ..Default::default()
+        };
+        let expect = Config {
+            ..Default::default()
+        };
+        c1.compose(&c2);
+        assert_eq!(c1, expect);
+    }
+
+    #[test]
+    fn test_resolve_overlapping_net_port_ranges() {
+        let config = Config {
+            handled_net: AccessNet::BindTcp | AccessNet::ConnectTcp,
+            rules_net_port: [
+                (PortRange::single(443), AccessNet::BindTcp.into()),
+                (
+                    PortRange {
+                        start: 440,
+                        end: 445,
+                    },
+                    AccessNet::ConnectTcp.into(),
+                ),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let resolved = config.resolve().unwrap();
+        assert_eq!(
+            resolved.rules_net_port,
+            [
+                (440, AccessNet::ConnectTcp.into()),
+                (441, AccessNet::ConnectTcp.into()),
+                (442, AccessNet::ConnectTcp.into()),
+                (443, AccessNet::BindTcp | AccessNet::ConnectTcp),
+                (444, AccessNet::ConnectTcp.into()),
+                (445, AccessNet::ConnectTcp.into()),
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_compose_v1_v2_without_one_right() {
+        let c1_access = AccessFs::from_all(ABI::V1);
+        let mut c1 = Config {
+            handled_fs: c1_access,
+            rules_path_beneath: [
+                (TemplateString::from_text("/common"), c1_access),
+                (TemplateString::from_text("/c1"), c1_access),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        assert!(c1_access.contains(AccessFs::WriteFile));
+        let c2_access = AccessFs::from_all(ABI::V2) & !AccessFs::WriteFile;
+        let c2 = Config {
+            handled_fs: c2_access,
+            rules_path_beneath: [
+                (TemplateString::from_text("/common"), c2_access),
+                (TemplateString::from_text("/c2"), c2_access),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        c1.compose(&c2);
+        assert_eq!(
+            c1,
+            Config {
+                handled_fs: c1_access & c2_access,
+                rules_path_beneath: [
+                    (TemplateString::from_text("/common"), c1_access & c2_access),
+                    (TemplateString::from_text("/c1"), c1_access & c2_access),
+                    (TemplateString::from_text("/c2"), c1_access & c2_access),
+                ]
+                .into(),
+                ..Default::default()
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_serialize {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_round_trip_json_path_beneath() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::ReadFile,
+            rules_path_beneath: [(
+                TemplateString::from_text("/usr/bin"),
+                AccessFs::Execute | AccessFs::ReadFile,
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let json = config.to_json_string().unwrap();
+        let parsed = Config::parse_json(json.as_bytes()).unwrap();
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn test_round_trip_json_net_port() {
+        let config = Config {
+            handled_net: AccessNet::BindTcp.into(),
+            rules_net_port: [(PortRange::single(8080), AccessNet::BindTcp.into())].into(),
+            ..Default::default()
+        };
+
+        let json = config.to_json_string().unwrap();
+        let parsed = Config::parse_json(json.as_bytes()).unwrap();
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn test_to_json_writer_matches_to_json_string() {
+        let config = Config {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(TemplateString::from_text("/bin"), AccessFs::Execute.into())]
+                .into(),
+            ..Default::default()
+        };
+
+        let mut buf = Vec::new();
+        config.to_json_writer(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            config.to_json_string().unwrap()
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_round_trip_toml() {
+        let config = Config {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(TemplateString::from_text("/bin"), AccessFs::Execute.into())]
+                .into(),
+            ..Default::default()
+        };
+
+        let toml = config.to_toml_string().unwrap();
+        let parsed = Config::parse_toml(&toml).unwrap();
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn test_to_json_empty() {
+        let config = Config::empty();
+        // An empty configuration has no sections to emit.
+        assert_eq!(config.to_json_string().unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_to_json_string_for_abi_folds_complete_set() {
+        let config = Config {
+            handled_fs: AccessFs::from_all(ABI::V3),
+            ..Default::default()
+        };
+
+        // Expanded by default, since no target ABI is given.
+        assert!(!config.to_json_string().unwrap().contains("abi.all"));
+
+        let json = config.to_json_string_for_abi(ABI::V3).unwrap();
+        assert!(json.contains("abi.all"));
+        assert_eq!(Config::parse_json(json.as_bytes()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_to_json_string_for_abi_keeps_partial_set_expanded() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::ReadFile,
+            ..Default::default()
+        };
+
+        let json = config.to_json_string_for_abi(ABI::V3).unwrap();
+        assert!(!json.contains("abi."));
+        assert_eq!(Config::parse_json(json.as_bytes()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_round_trip_json_resolved_config() {
+        let resolved = Config {
+            handled_fs: AccessFs::Execute | AccessFs::ReadFile,
+            rules_path_beneath: [(
+                TemplateString::from_text("/usr/bin"),
+                AccessFs::Execute | AccessFs::ReadFile,
+            )]
+            .into(),
+            handled_net: AccessNet::BindTcp.into(),
+            rules_net_port: [(PortRange::single(8080), AccessNet::BindTcp.into())].into(),
+            ..Default::default()
+        }
+        .resolve()
+        .unwrap();
+
+        let json = resolved.to_json_string().unwrap();
+        let parsed = Config::parse_json(json.as_bytes())
+            .unwrap()
+            .resolve()
+            .unwrap();
+        assert_eq!(parsed, resolved);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_round_trip_toml_resolved_config() {
+        let resolved = Config {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(TemplateString::from_text("/bin"), AccessFs::Execute.into())]
+                .into(),
+            ..Default::default()
+        }
+        .resolve()
+        .unwrap();
+
+        let toml = resolved.to_toml_string().unwrap();
+        let parsed = Config::parse_toml(&toml).unwrap().resolve().unwrap();
+        assert_eq!(parsed, resolved);
+    }
+}
+
+#[cfg(test)]
+mod tests_abi_downgrade {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_no_downgrade_needed() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::ReadFile,
+            rules_path_beneath: [(
+                TemplateString::from_text("/bin"),
+                AccessFs::Execute | AccessFs::ReadFile,
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let (resolved, report) = config.clone().resolve_for_abi(ABI::V1).unwrap();
+        assert!(report.is_empty());
+        assert_eq!(resolved, config.resolve().unwrap());
+    }
+
+    #[test]
+    fn test_drops_unsupported_handled_right() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
+            ..Default::default()
+        };
+
+        let (resolved, report) = config.resolve_for_abi(ABI::V1).unwrap();
+        assert_eq!(resolved.handled_fs, AccessFs::Execute.into());
+        assert_eq!(report.dropped.len(), 1);
+        assert_eq!(report.dropped[0].minimum_abi, ABI::V2);
+    }
+
+    #[test]
+    fn test_drops_rule_with_no_remaining_access() {
+        let config = Config {
+            handled_fs: AccessFs::Refer.into(),
+            rules_path_beneath: [(TemplateString::from_text("/bin"), AccessFs::Refer.into())]
+                .into(),
+            ..Default::default()
+        };
+
+        let (resolved, report) = config.resolve_for_abi(ABI::V1).unwrap();
+        assert!(resolved.rules_path_beneath.is_empty());
+        assert!(report
+            .dropped
+            .iter()
+            .any(|dropped| dropped.description.contains("pathBeneath")));
+    }
+
+    #[test]
+    fn test_drops_net_access_below_v4() {
+        let config = Config {
+            handled_net: AccessNet::BindTcp.into(),
+            rules_net_port: [(PortRange::single(443), AccessNet::BindTcp.into())].into(),
+            ..Default::default()
+        };
+
+        let (resolved, report) = config.resolve_for_abi(ABI::V3).unwrap();
+        assert!(resolved.rules_net_port.is_empty());
+        assert!(report
+            .dropped
+            .iter()
+            .any(|dropped| dropped.minimum_abi == ABI::V4));
+    }
+}
+
+#[cfg(test)]
+mod tests_validate_for_abi {
+    use super::*;
+
+    #[test]
+    fn test_fully_compatible_config() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::ReadFile,
+            rules_path_beneath: [(
+                TemplateString::from_text("/bin"),
+                AccessFs::Execute | AccessFs::ReadFile,
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let report = config.validate_for_abi(ABI::V1).unwrap();
+        assert!(report.unsupported.is_empty());
+    }
+
+    #[test]
+    fn test_reports_right_requiring_newer_abi() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
+            ..Default::default()
+        };
+
+        let report = config.validate_for_abi(ABI::V1).unwrap();
+        assert_eq!(report.unsupported.dropped.len(), 1);
+        assert_eq!(report.unsupported.dropped[0].minimum_abi, ABI::V2);
+    }
+
+    #[test]
+    fn test_does_not_mutate_the_config() {
+        let config = Config {
+            handled_fs: AccessFs::Refer.into(),
+            ..Default::default()
+        };
+
+        let report = config.validate_for_abi(ABI::V1).unwrap();
+        assert_eq!(report.unsupported.dropped.len(), 1);
+        // Unlike resolve_for_abi, the rights above target are still handled.
+        assert_eq!(config.handled_fs, AccessFs::Refer.into());
+    }
+}
+
+#[cfg(test)]
+mod tests_build_ruleset {
+    use super::*;
+
+    #[test]
+    fn test_build_ruleset_resolves_and_builds() {
+        let config = Config {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        };
+
+        let (_ruleset, rule_errors) = config.build_ruleset().unwrap();
+        assert!(rule_errors.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_compat_level {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_hard_requirement_errors_on_unsupported_right() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
+            ..Default::default()
+        };
+
+        let err = config
+            .build_ruleset_for_abi(ABI::V1, CompatLevel::HardRequirement)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuildRulesetError::Unsupported {
+                minimum_abi: ABI::V2,
+                detected: ABI::V1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_hard_requirement_builds_when_fully_supported() {
+        let config = Config {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        };
+
+        let (_ruleset, rule_errors, report) = config
+            .build_ruleset_for_abi(ABI::V1, CompatLevel::HardRequirement)
+            .unwrap();
+        assert!(rule_errors.is_empty());
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_best_effort_downgrades_and_builds() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
+            ..Default::default()
+        };
+
+        let (_ruleset, _rule_errors, report) = config
+            .build_ruleset_for_abi(ABI::V1, CompatLevel::BestEffort)
+            .unwrap();
+        assert_eq!(report.dropped.len(), 1);
+        assert_eq!(report.dropped[0].minimum_abi, ABI::V2);
+    }
+
+    #[test]
+    fn test_soft_requirement_errors_when_everything_dropped() {
+        let config = Config {
+            handled_fs: AccessFs::Refer.into(),
+            ..Default::default()
+        };
+
+        let err = config
+            .build_ruleset_for_abi(ABI::V1, CompatLevel::SoftRequirement)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuildRulesetError::Unsupported {
+                detected: ABI::V1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_soft_requirement_builds_when_partial_support_remains() {
+        let config = Config {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
+            ..Default::default()
+        };
+
+        let (_ruleset, _rule_errors, report) = config
+            .build_ruleset_for_abi(ABI::V1, CompatLevel::SoftRequirement)
+            .unwrap();
+        assert_eq!(report.dropped.len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod tests_negotiate_abi {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_hard_requirement_errors_on_unsupported_right() {
+        let resolved = Config {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
+            ..Default::default()
+        }
+        .resolve()
+        .unwrap();
+
+        let err = resolved
+            .negotiate_abi(ABI::V1, CompatLevel::HardRequirement)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuildRulesetError::Unsupported {
+                minimum_abi: ABI::V2,
+                detected: ABI::V1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_hard_requirement_leaves_config_untouched_when_supported() {
+        let resolved = Config {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        }
+        .resolve()
+        .unwrap();
+
+        let (negotiated, report) = resolved
+            .negotiate_abi(ABI::V1, CompatLevel::HardRequirement)
+            .unwrap();
+        assert_eq!(negotiated, resolved);
+        assert!(report.dropped.is_empty());
+        assert_eq!(report.negotiated_abi, ABI::V1);
+        assert_eq!(report.compat, CompatLevel::HardRequirement);
+    }
+
+    #[test]
+    fn test_best_effort_downgrades_silently() {
+        let resolved = Config {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
+            ..Default::default()
+        }
+        .resolve()
+        .unwrap();
+
+        let (negotiated, report) = resolved
+            .negotiate_abi(ABI::V1, CompatLevel::BestEffort)
+            .unwrap();
+        assert_eq!(negotiated.handled_fs, AccessFs::Execute.into());
+        assert_eq!(report.dropped.dropped.len(), 1);
+        assert_eq!(report.dropped.dropped[0].minimum_abi, ABI::V2);
+        assert_eq!(report.compat, CompatLevel::BestEffort);
+    }
+
+    #[test]
+    fn test_soft_requirement_errors_when_everything_dropped() {
+        let resolved = Config {
+            handled_fs: AccessFs::Refer.into(),
+            ..Default::default()
+        }
+        .resolve()
+        .unwrap();
+
+        let err = resolved
+            .negotiate_abi(ABI::V1, CompatLevel::SoftRequirement)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuildRulesetError::Unsupported {
+                detected: ABI::V1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_soft_requirement_downgrades_when_partial_support_remains() {
+        let resolved = Config {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
+            ..Default::default()
+        }
+        .resolve()
+        .unwrap();
+
+        let (negotiated, report) = resolved
+            .negotiate_abi(ABI::V1, CompatLevel::SoftRequirement)
+            .unwrap();
+        assert_eq!(negotiated.handled_fs, AccessFs::Execute.into());
+        assert_eq!(report.dropped.dropped.len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod tests_diagnostic {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_unknown_field_diagnostic() {
+        let json = r#"{
+            "ruleset": [
+                { "handledAccessFs": [ "execute" ], "bogus": true }
+            ]
+        }"#;
+        let err = Config::parse_json(json.as_bytes()).unwrap_err();
+        let diagnostic = err.diagnostic();
+        assert_eq!(diagnostic.kind, DiagnosticKind::UnknownField);
+        assert_eq!(diagnostic.path.as_deref(), Some("bogus"));
+    }
+
+    #[test]
+    fn test_empty_collection_diagnostic() {
+        let json = r#"{
+            "ruleset": [
+                { "handledAccessFs": [] }
+            ]
+        }"#;
+        let err = Config::parse_json(json.as_bytes()).unwrap_err();
+        assert_eq!(err.diagnostic().kind, DiagnosticKind::EmptyCollection);
+    }
+
+    #[test]
+    fn test_syntax_error_classify() {
+        let err = Config::parse_json("not json".as_bytes()).unwrap_err();
+        assert_eq!(err.classify(), serde_json::error::Category::Syntax);
+        assert_eq!(err.diagnostic().kind, DiagnosticKind::Syntax);
+    }
+
+    #[test]
+    fn test_parse_json_with_diagnostics_reports_error() {
+        let mut diagnostics = Diagnostics::new();
+        let result = Config::parse_json_with_diagnostics("not json".as_bytes(), &mut diagnostics);
+
+        assert!(result.is_err());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics.iter().next().unwrap().severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_parse_json_with_diagnostics_silent_on_success() {
+        let mut diagnostics = Diagnostics::new();
+        let result = Config::parse_json_with_diagnostics("{}".as_bytes(), &mut diagnostics);
+
+        assert!(result.is_ok());
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_build_ruleset_with_diagnostics_reports_rule_error_as_warning() {
+        let resolved = ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(
+                PathBuf::from("/does/not/exist/landlockconfig"),
+                AccessFs::Execute.into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let mut diagnostics = Diagnostics::new();
+        resolved
+            .build_ruleset_with_diagnostics(&mut diagnostics)
+            .unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = diagnostics.iter().next().unwrap();
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.code, "rule_error");
+        assert_eq!(
+            diagnostic.subject.as_deref(),
+            Some("/does/not/exist/landlockconfig")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_merge {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_merge_unions_handled_access() {
+        let mut c1 = Config {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        };
+        let c2 = Config {
+            handled_net: AccessNet::BindTcp.into(),
+            ..Default::default()
+        };
+
+        c1.merge(&c2).unwrap();
+        assert_eq!(c1.handled_fs, AccessFs::Execute.into());
+        assert_eq!(c1.handled_net, AccessNet::BindTcp.into());
+    }
+
+    #[test]
+    fn test_merge_ors_overlapping_rules() {
+        let mut c1 = Config {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(TemplateString::from_text("/bin"), AccessFs::Execute.into())]
+                .into(),
+            ..Default::default()
+        };
+        let c2 = Config {
+            handled_fs: AccessFs::ReadFile.into(),
+            rules_path_beneath: [(TemplateString::from_text("/bin"), AccessFs::ReadFile.into())]
+                .into(),
+            ..Default::default()
+        };
+
+        c1.merge(&c2).unwrap();
+        assert_eq!(
+            c1.rules_path_beneath[&TemplateString::from_text("/bin")],
+            AccessFs::Execute | AccessFs::ReadFile
+        );
+    }
+
+    #[test]
+    fn test_merge_same_abi_ok() {
+        let mut c1 = Config {
+            abi: Some(AbiRequirement::Exact(ABI::V4)),
+            ..Default::default()
+        };
+        let c2 = Config {
+            abi: Some(AbiRequirement::Exact(ABI::V4)),
+            ..Default::default()
+        };
+
+        c1.merge(&c2).unwrap();
+        assert_eq!(c1.abi, Some(AbiRequirement::Exact(ABI::V4)));
+    }
+
+    #[test]
+    fn test_merge_keeps_abi_present_on_one_side() {
+        let mut c1 = Config {
+            abi: Some(AbiRequirement::Exact(ABI::V3)),
+            ..Default::default()
+        };
+        c1.merge(&Config::default()).unwrap();
+        assert_eq!(c1.abi, Some(AbiRequirement::Exact(ABI::V3)));
+
+        let mut c2 = Config::default();
+        c2.merge(&Config {
+            abi: Some(AbiRequirement::Exact(ABI::V3)),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(c2.abi, Some(AbiRequirement::Exact(ABI::V3)));
+    }
+
+    #[test]
+    fn test_merge_conflicting_abi_rejected() {
+        let mut c1 = Config {
+            abi: Some(AbiRequirement::Exact(ABI::V2)),
+            ..Default::default()
+        };
+        let c2 = Config {
+            abi: Some(AbiRequirement::Exact(ABI::V4)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            c1.merge(&c2),
+            Err(MergeError::ConflictingAbi {
+                a: AbiRequirement::Exact(ABI::V2),
+                b: AbiRequirement::Exact(ABI::V4),
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_layers_merges_in_order() {
+        let base = Config {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        };
+        let overlay = Config {
+            handled_net: AccessNet::BindTcp.into(),
+            ..Default::default()
+        };
+
+        let merged = Config::from_layers([base, overlay]).unwrap();
+        assert_eq!(merged.handled_fs, AccessFs::Execute.into());
+        assert_eq!(merged.handled_net, AccessNet::BindTcp.into());
+    }
+
+    #[test]
+    fn test_from_layers_conflicting_abi_rejected() {
+        let base = Config {
+            abi: Some(AbiRequirement::Exact(ABI::V2)),
+            ..Default::default()
+        };
+        let overlay = Config {
+            abi: Some(AbiRequirement::Exact(ABI::V4)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Config::from_layers([base, overlay]),
+            Err(MergeError::ConflictingAbi {
+                a: AbiRequirement::Exact(ABI::V2),
+                b: AbiRequirement::Exact(ABI::V4),
+            })
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_check {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_check_reports_missing_path() {
+        let resolved = ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(
+                PathBuf::from("/does/not/exist/landlockconfig"),
+                AccessFs::Execute.into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let report = resolved.check();
+        assert_eq!(report.path_issues.len(), 1);
+        assert_eq!(
+            report.path_issues[0].path,
+            PathBuf::from("/does/not/exist/landlockconfig")
+        );
+    }
+
+    #[test]
+    fn test_check_passes_existing_path() {
+        let resolved = ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        };
+
+        assert!(resolved.check().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests_validate {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_valid_config_has_no_errors() {
+        let config = Config {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(TemplateString::from_text("/bin"), AccessFs::Execute.into())]
+                .into(),
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_unhandled_path_access_reported() {
+        let config = Config {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(
+                TemplateString::from_text("/bin"),
+                AccessFs::Execute | AccessFs::ReadFile,
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let errors = config.validate();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::UnhandledPathAccess { path, access }
+                if path == "/bin" && *access == BitFlags::from(AccessFs::ReadFile)
+        ));
+    }
+
+    #[test]
+    fn test_unhandled_net_access_reported() {
+        let config = Config {
+            handled_fs: AccessFs::Execute.into(),
+            rules_net_port: [(PortRange::single(443), AccessNet::BindTcp.into())].into(),
+            ..Default::default()
+        };
+
+        let errors = config.validate();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::UnhandledNetAccess { port, access }
+                if port == "443" && *access == BitFlags::from(AccessNet::BindTcp)
+        ));
+    }
+
+    #[test]
+    fn test_empty_ruleset_reported() {
+        let errors = Config::empty().validate();
+        assert_eq!(errors, vec![ValidationError::EmptyRuleset]);
     }
 
     #[test]
-    fn test_different_ruleset() {
-        let mut c1 = Config {
+    fn test_validate_with_paths_reports_missing_parent() {
+        let config = Config {
             handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(
+                TemplateString::from_text("/does/not/exist/landlockconfig"),
+                AccessFs::Execute.into(),
+            )]
+            .into(),
             ..Default::default()
         };
-        let c2 = Config {
-            handled_net: AccessNet::BindTcp.into(),
+
+        let errors = config.validate_with_paths().unwrap();
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, ValidationError::PathNotFound { path, .. } if path == std::path::Path::new("/does/not/exist/landlockconfig"))));
+    }
+
+    #[test]
+    fn test_validate_with_paths_reports_directory_only_access_on_file() {
+        let path = std::env::temp_dir().join(format!(
+            "landlockconfig-test-{}-directory-only-access",
+            std::process::id()
+        ));
+        fs::write(&path, b"").unwrap();
+
+        let config = Config {
+            handled_fs: AccessFs::ReadDir | AccessFs::MakeDir,
+            rules_path_beneath: [(
+                TemplateString::from_text(path.to_str().unwrap()),
+                AccessFs::ReadDir | AccessFs::MakeDir,
+            )]
+            .into(),
             ..Default::default()
         };
-        let expect = Config {
+
+        let errors = config.validate_with_paths().unwrap();
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::DirectoryOnlyAccessOnFile { path: p, access }
+                if p == &path && *access == (BitFlags::from(AccessFs::ReadDir) | AccessFs::MakeDir)
+        )));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_validate_with_paths_allows_directory_only_access_on_directory() {
+        let config = Config {
+            handled_fs: AccessFs::ReadDir.into(),
+            rules_path_beneath: [(TemplateString::from_text("/"), AccessFs::ReadDir.into())].into(),
             ..Default::default()
         };
-        c1.compose(&c2);
-        assert_eq!(c1, expect);
+
+        let errors = config.validate_with_paths().unwrap();
+        assert!(errors.is_empty());
     }
 
     #[test]
-    fn test_compose_v1_v2_without_one_right() {
-        let c1_access = AccessFs::from_all(ABI::V1);
-        let mut c1 = Config {
-            handled_fs: c1_access,
-            rules_path_beneath: [
-                (TemplateString::from_text("/common"), c1_access),
-                (TemplateString::from_text("/c1"), c1_access),
-            ]
-            .into(),
+    fn test_validation_error_codes_are_distinct() {
+        assert_eq!(ValidationError::EmptyRuleset.code(), "empty_ruleset");
+        assert_eq!(
+            ValidationError::UnhandledPathAccess {
+                path: "/bin".to_string(),
+                access: AccessFs::ReadFile.into(),
+            }
+            .code(),
+            "unhandled_path_access"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_compatibility {
+    use super::*;
+    use landlock::Access;
+
+    #[test]
+    fn test_fully_compatible() {
+        let resolved = ResolvedConfig {
+            handled_fs: AccessFs::from_all(ABI::V1),
             ..Default::default()
         };
 
-        assert!(c1_access.contains(AccessFs::WriteFile));
-        let c2_access = AccessFs::from_all(ABI::V2) & !AccessFs::WriteFile;
-        let c2 = Config {
-            handled_fs: c2_access,
-            rules_path_beneath: [
-                (TemplateString::from_text("/common"), c2_access),
-                (TemplateString::from_text("/c2"), c2_access),
-            ]
-            .into(),
+        let report = resolved.compatibility_report(ABI::V1);
+        assert!(report.is_fully_compatible());
+    }
+
+    #[test]
+    fn test_reports_unsupported_right() {
+        let resolved = ResolvedConfig {
+            handled_fs: AccessFs::Execute | AccessFs::Refer,
             ..Default::default()
         };
 
-        c1.compose(&c2);
-        assert_eq!(
-            c1,
-            Config {
-                handled_fs: c1_access & c2_access,
-                rules_path_beneath: [
-                    (TemplateString::from_text("/common"), c1_access & c2_access),
-                    (TemplateString::from_text("/c1"), c1_access & c2_access),
-                    (TemplateString::from_text("/c2"), c1_access & c2_access),
+        let report = resolved.compatibility_report(ABI::V1);
+        assert_eq!(report.unsupported.dropped.len(), 1);
+        assert_eq!(report.unsupported.dropped[0].minimum_abi, ABI::V2);
+        assert!(!report.is_fully_compatible());
+    }
+
+    #[test]
+    fn test_reports_unused_right() {
+        let resolved = ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        };
+
+        let report = resolved.compatibility_report(ABI::V1);
+        assert!(report.unsupported.is_empty());
+        assert!(report.unused_fs.contains(AccessFs::ReadFile));
+        assert!(!report.is_fully_compatible());
+    }
+}
+
+#[cfg(test)]
+mod tests_include {
+    use super::*;
+
+    fn unique_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "landlockconfig-test-{}-{}-{name}",
+            std::process::id(),
+            name.len()
+        ))
+    }
+
+    #[test]
+    fn test_parse_json_file_merges_includes() {
+        let dir = unique_path("merges-includes");
+        fs::create_dir_all(&dir).unwrap();
+
+        let base_path = dir.join("base.json");
+        fs::write(
+            &base_path,
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#,
+        )
+        .unwrap();
+
+        let main_path = dir.join("main.json");
+        fs::write(
+            &main_path,
+            r#"{
+                "include": [ "base.json" ],
+                "ruleset": [ { "handledAccessNet": [ "bind_tcp" ] } ]
+            }"#,
+        )
+        .unwrap();
+
+        let config = Config::parse_json_file(&main_path).unwrap();
+        assert_eq!(config.handled_fs, AccessFs::Execute.into());
+        assert_eq!(config.handled_net, AccessNet::BindTcp.into());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_json_file_detects_cycle() {
+        let dir = unique_path("detects-cycle");
+        fs::create_dir_all(&dir).unwrap();
+
+        let a_path = dir.join("a.json");
+        let b_path = dir.join("b.json");
+        fs::write(
+            &a_path,
+            r#"{
+                "include": [ "b.json" ],
+                "ruleset": [ { "handledAccessFs": [ "execute" ] } ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            &b_path,
+            r#"{
+                "include": [ "a.json" ],
+                "ruleset": [ { "handledAccessFs": [ "read_file" ] } ]
+            }"#,
+        )
+        .unwrap();
+
+        let err = Config::parse_json_file(&a_path).unwrap_err();
+        assert!(matches!(err, ParseIncludeError::Cycle(_)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests_profiles {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+        "profiles": [
+            {
+                "name": "dev",
+                "ruleset": [ { "handledAccessNet": [ "bind_tcp" ] } ]
+            },
+            {
+                "name": "prod",
+                "pathBeneath": [
+                    { "allowedAccess": [ "read_file" ], "parent": [ "/etc" ] }
                 ]
-                .into(),
-                ..Default::default()
             }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_json_with_profile_unions_onto_base() {
+        let config = Config::parse_json_with_profile(JSON.as_bytes(), "dev").unwrap();
+        assert_eq!(config.handled_fs, AccessFs::Execute.into());
+        assert_eq!(config.handled_net, AccessNet::BindTcp.into());
+    }
+
+    #[test]
+    fn test_parse_json_with_profile_selects_the_right_one() {
+        let config = Config::parse_json_with_profile(JSON.as_bytes(), "prod").unwrap();
+        assert_eq!(config.handled_net, BitFlags::EMPTY);
+        assert!(config
+            .rules_path_beneath
+            .contains_key(&TemplateString::from_text("/etc")));
+    }
+
+    #[test]
+    fn test_parse_json_with_profile_unknown_name() {
+        let err = Config::parse_json_with_profile(JSON.as_bytes(), "staging").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseJsonError::Config(ConfigError::Profile(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_json_without_profile_ignores_profiles() {
+        let config = Config::parse_json(JSON.as_bytes()).unwrap();
+        assert_eq!(config.handled_fs, AccessFs::Execute.into());
+        assert_eq!(config.handled_net, BitFlags::EMPTY);
+    }
+}
+
+#[cfg(test)]
+mod tests_format_source {
+    use super::*;
+
+    const JSON: &str = r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#;
+
+    #[test]
+    fn test_parse_source_json_matches_parse_json() {
+        let config = Config::parse_source(&JsonFormat, JSON).unwrap();
+        assert_eq!(config, Config::parse_json(JSON.as_bytes()).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn test_parse_source_toml_matches_parse_toml() {
+        const TOML: &str = "[[ruleset]]\nhandledAccessFs = [\"execute\"]\n";
+        let config = Config::parse_source(&TomlFormat, TOML).unwrap();
+        assert_eq!(config, Config::parse_toml(TOML).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests_discover {
+    use super::*;
+
+    fn unique_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "landlockconfig-test-{}-{}-{name}",
+            std::process::id(),
+            name.len()
+        ))
+    }
+
+    #[test]
+    fn test_discover_composes_ancestors_outermost_first() {
+        let root = unique_path("discover-composes");
+        let leaf = root.join("home").join("repo");
+        fs::create_dir_all(&leaf).unwrap();
+
+        fs::write(
+            root.join("home").join(".landlock.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ], "handledAccessNet": [ "bind_tcp" ] } ] }"#,
+        )
+        .unwrap();
+        fs::write(
+            leaf.join(".landlock.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#,
+        )
+        .unwrap();
+
+        let config = Config::discover(&leaf, ConfigFormat::Json).unwrap();
+        // The repo-local file only handles fs, so composing tightens away
+        // the home directory's net handling.
+        assert_eq!(config.handled_fs, AccessFs::Execute.into());
+        assert_eq!(config.handled_net, BitFlags::EMPTY);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_returns_empty_config_when_no_file_found() {
+        let dir = unique_path("discover-empty");
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = Config::discover(&dir, ConfigFormat::Json).unwrap();
+        assert_eq!(config, Config::empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_discover_skips_missing_levels() {
+        let root = unique_path("discover-skips");
+        let leaf = root.join("a").join("b");
+        fs::create_dir_all(&leaf).unwrap();
+
+        fs::write(
+            root.join("a").join(".landlock.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "read_file" ] } ] }"#,
+        )
+        .unwrap();
+
+        let config = Config::discover(&leaf, ConfigFormat::Json).unwrap();
+        assert_eq!(config.handled_fs, AccessFs::ReadFile.into());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_merged_unions_ancestors_instead_of_tightening() {
+        let root = unique_path("discover-merged-unions");
+        let leaf = root.join("home").join("repo");
+        fs::create_dir_all(&leaf).unwrap();
+
+        fs::write(
+            root.join("home").join(".landlock.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ], "handledAccessNet": [ "bind_tcp" ] } ] }"#,
+        )
+        .unwrap();
+        fs::write(
+            leaf.join(".landlock.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "read_file" ] } ] }"#,
+        )
+        .unwrap();
+
+        let config = Config::discover_merged(&leaf, ConfigFormat::Json).unwrap();
+        // Unlike `discover`, the repo-local file's fs handling adds to the
+        // home directory's rather than narrowing away its net handling.
+        assert_eq!(config.handled_fs, AccessFs::Execute | AccessFs::ReadFile);
+        assert_eq!(config.handled_net, AccessNet::BindTcp.into());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_merged_returns_empty_config_when_no_file_found() {
+        let dir = unique_path("discover-merged-empty");
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = Config::discover_merged(&dir, ConfigFormat::Json).unwrap();
+        assert_eq!(config, Config::empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests_provenance {
+    use super::*;
+
+    fn unique_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "landlockconfig-test-{}-{}-{name}",
+            std::process::id(),
+            name.len()
+        ))
+    }
+
+    #[test]
+    fn test_untagged_config_has_no_origins() {
+        let config = Config::parse_json(
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(config.origins_for_access(AccessFs::Execute), &[]);
+    }
+
+    #[test]
+    fn test_with_source_tags_access_and_rule() {
+        let config = Config::parse_json(
+            r#"{
+                "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+                "pathBeneath": [ { "allowedAccess": [ "execute" ], "parent": [ "/bin" ] } ]
+            }"#
+            .as_bytes(),
+        )
+        .unwrap()
+        .with_source(Source::Label("a".to_string()));
+
+        assert_eq!(
+            config.origins_for_access(AccessFs::Execute),
+            &[Source::Label("a".to_string())]
+        );
+        let path = TemplateString::from_text("/bin");
+        assert_eq!(
+            config.origins_for_rule(&path),
+            &[Source::Label("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_compose_unions_origins_for_common_access() {
+        let a = Config::parse_json(
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute", "read_file" ] } ] }"#.as_bytes(),
+        )
+        .unwrap()
+        .with_source(Source::Label("a".to_string()));
+        let b = Config::parse_json(
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#.as_bytes(),
+        )
+        .unwrap()
+        .with_source(Source::Label("b".to_string()));
+
+        let mut composed = a;
+        composed.compose(&b);
+
+        assert_eq!(
+            composed.origins_for_access(AccessFs::Execute),
+            &[
+                Source::Label("a".to_string()),
+                Source::Label("b".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compose_drops_origins_for_access_outside_intersection() {
+        let a = Config::parse_json(
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute", "read_file" ] } ] }"#.as_bytes(),
+        )
+        .unwrap()
+        .with_source(Source::Label("a".to_string()));
+        let b = Config::parse_json(
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#.as_bytes(),
+        )
+        .unwrap()
+        .with_source(Source::Label("b".to_string()));
+
+        let mut composed = a;
+        composed.compose(&b);
+
+        // ReadFile fell out of the intersection, so its provenance goes too.
+        assert_eq!(composed.origins_for_access(AccessFs::ReadFile), &[]);
+    }
+
+    #[test]
+    fn test_from_layers_tracks_origins_for_access_and_rule() {
+        let base = Config::parse_json(
+            r#"{
+                "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+                "pathBeneath": [ { "allowedAccess": [ "execute" ], "parent": [ "/bin" ] } ]
+            }"#
+            .as_bytes(),
+        )
+        .unwrap()
+        .with_source(Source::Label("base".to_string()));
+        let overlay = Config::parse_json(
+            r#"{ "ruleset": [ { "handledAccessFs": [ "read_file" ] } ] }"#.as_bytes(),
+        )
+        .unwrap()
+        .with_source(Source::Label("overlay".to_string()));
+
+        let merged = Config::from_layers([base, overlay]).unwrap();
+
+        assert_eq!(
+            merged.origins_for_access(AccessFs::Execute),
+            &[Source::Label("base".to_string())]
+        );
+        assert_eq!(
+            merged.origins_for_access(AccessFs::ReadFile),
+            &[Source::Label("overlay".to_string())]
+        );
+        let path = TemplateString::from_text("/bin");
+        assert_eq!(
+            merged.origins_for_rule(&path),
+            &[Source::Label("base".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_resolve_exposes_origins_by_concrete_path() {
+        let config = Config::parse_json(
+            r#"{
+                "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+                "pathBeneath": [ { "allowedAccess": [ "execute" ], "parent": [ "/bin" ] } ]
+            }"#
+            .as_bytes(),
+        )
+        .unwrap()
+        .with_source(Source::Label("a".to_string()));
+
+        let resolved = config.resolve().unwrap();
+        assert_eq!(
+            resolved.origins_for_path(Path::new("/bin")),
+            &[Source::Label("a".to_string())]
+        );
+        assert_eq!(
+            resolved.origins_for_access(AccessFs::Execute),
+            &[Source::Label("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_directory_tags_each_rule_with_its_file() {
+        let dir = unique_path("provenance-parse-directory");
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(
+            dir.join("a.json"),
+            r#"{
+                "ruleset": [ { "handledAccessFs": [ "execute", "read_file" ] } ],
+                "pathBeneath": [ { "allowedAccess": [ "execute" ], "parent": [ "/bin" ] } ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("b.json"),
+            r#"{
+                "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+                "pathBeneath": [ { "allowedAccess": [ "execute" ], "parent": [ "/usr" ] } ]
+            }"#,
+        )
+        .unwrap();
+
+        let config = Config::parse_directory(&dir, ConfigFormat::Json).unwrap();
+
+        let bin = TemplateString::from_text("/bin");
+        let usr = TemplateString::from_text("/usr");
+        assert_eq!(
+            config.origins_for_rule(&bin),
+            &[Source::File(dir.join("a.json"))]
+        );
+        assert_eq!(
+            config.origins_for_rule(&usr),
+            &[Source::File(dir.join("b.json"))]
+        );
+        assert_eq!(
+            config.origins_for_access(AccessFs::Execute),
+            &[
+                Source::File(dir.join("a.json")),
+                Source::File(dir.join("b.json"))
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests_parse_directory_recursive {
+    use super::*;
+
+    fn unique_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "landlockconfig-test-{}-{}-{name}",
+            std::process::id(),
+            name.len()
+        ))
+    }
+
+    #[test]
+    fn test_composes_nested_subdirectories() {
+        let dir = unique_path("recursive-nested");
+        let sub = dir.join("sub");
+        fs::create_dir_all(&sub).unwrap();
+
+        fs::write(
+            dir.join("a.json"),
+            r#"{
+                "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+                "pathBeneath": [ { "allowedAccess": [ "execute" ], "parent": [ "/bin" ] } ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            sub.join("b.json"),
+            r#"{
+                "pathBeneath": [ { "allowedAccess": [ "execute" ], "parent": [ "/usr" ] } ]
+            }"#,
+        )
+        .unwrap();
+
+        let config = Config::parse_directory_recursive(&dir).unwrap();
+        let resolved = config.resolve().unwrap();
+        assert_eq!(
+            resolved.rules_path_beneath,
+            [
+                (PathBuf::from("/bin"), AccessFs::Execute.into()),
+                (PathBuf::from("/usr"), AccessFs::Execute.into()),
+            ]
+            .into()
         );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_skips_dotfiles_and_dot_directories() {
+        let dir = unique_path("recursive-dotfiles");
+        let hidden_dir = dir.join(".hidden");
+        fs::create_dir_all(&hidden_dir).unwrap();
+
+        fs::write(
+            dir.join(".a.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#,
+        )
+        .unwrap();
+        fs::write(
+            hidden_dir.join("b.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "read_file" ] } ] }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("c.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "write_file" ] } ] }"#,
+        )
+        .unwrap();
+
+        let config = Config::parse_directory_recursive(&dir).unwrap();
+        let resolved = config.resolve().unwrap();
+        assert_eq!(resolved.handled_fs, AccessFs::WriteFile.into());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_mixes_json_and_toml_fragments() {
+        let dir = unique_path("recursive-mixed-format");
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(
+            dir.join("a.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("b.toml"),
+            "[[ruleset]]\nhandledAccessFs = [\"read_file\"]\n",
+        )
+        .unwrap();
+
+        let config = Config::parse_directory_recursive(&dir).unwrap();
+        let resolved = config.resolve().unwrap();
+        assert_eq!(resolved.handled_fs, AccessFs::Execute | AccessFs::ReadFile);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_same_stem_in_both_formats_is_ambiguous() {
+        let dir = unique_path("recursive-ambiguous-stem");
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(
+            dir.join("a.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("a.toml"),
+            "[[ruleset]]\nhandledAccessFs = [\"read_file\"]\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            Config::parse_directory_recursive(&dir),
+            Err(ParseDirectoryError::AmbiguousSource { .. })
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_idempotent_with_intersect_mode() {
+        let dir = unique_path("recursive-idempotent");
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(
+            dir.join("a.json"),
+            r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#,
+        )
+        .unwrap();
+
+        let once = Config::parse_directory_recursive(&dir).unwrap();
+        let mut composed_twice = once.clone();
+        composed_twice.compose(&once);
+        assert_eq!(composed_twice, once);
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,382 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Structured, locatable diagnostics for configuration parse failures.
+//!
+//! [`crate::config::ParseJsonError`] and [`crate::config::ParseTomlError`]
+//! otherwise collapse every failure into an opaque `serde_json`/`toml`
+//! error, which is only good for a coarse [`serde_json::error::Category`].
+//! [`ParseDiagnostic`] extracts what structure `serde` exposes in its error
+//! messages (source line/column, the offending field or variant name, and
+//! the accepted variants for enum fields) into a stable, serializable shape
+//! that editors and CI annotations can consume directly.
+
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+
+/// Broad classification of a parse failure, replacing the single
+/// `serde_json::error::Category::Data` bucket with named, distinguishable
+/// kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// A required field was missing from an object.
+    MissingField,
+    /// A field not defined by the schema was present.
+    UnknownField,
+    /// A string or tag did not match any of the accepted variants.
+    UnknownVariant,
+    /// A set that must be non-empty (e.g. a `NonEmptySet`) was empty.
+    EmptyCollection,
+    /// The input was not syntactically valid JSON/TOML.
+    Syntax,
+    /// Any other structural error not classified above.
+    Other,
+}
+
+/// A single, machine-readable parse diagnostic.
+///
+/// Serializes to a stable JSON object with `path`, `line`, `col`, `kind`,
+/// `message`, and an optional `expected` array of accepted variant names,
+/// suitable for editor integration or CI annotations. [`fmt::Display`]
+/// renders the same information for humans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseDiagnostic {
+    pub kind: DiagnosticKind,
+    pub message: String,
+    /// Best-effort path to the offending node.
+    ///
+    /// This is only ever the single field/variant name `serde` mentions in
+    /// its message: full JSON Pointer tracking through nested structures
+    /// would require walking the deserialization with something like
+    /// `serde_path_to_error`.
+    pub path: Option<String>,
+    pub line: Option<usize>,
+    pub col: Option<usize>,
+    pub expected: Option<Vec<String>>,
+    /// Byte offsets of the offending value in the original source, when the
+    /// underlying format exposes one. `serde_json` only ever gives us
+    /// line/column, so this is `None` for JSON; TOML errors carry a span we
+    /// can resolve to line/column ourselves via [`line_col_from_offset`].
+    pub span: Option<Range<usize>>,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(line), Some(col)) = (self.line, self.col) {
+            write!(f, "{line}:{col}: ")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "{path}: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(expected) = &self.expected {
+            write!(f, " (expected one of: {})", expected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl ParseDiagnostic {
+    /// Renders a one-line, caret-underlined snippet of `source` pointing at
+    /// this diagnostic's location, e.g.:
+    ///
+    /// ```text
+    /// 3 |     "abi": 0,
+    ///   |            ^^
+    /// ```
+    ///
+    /// Returns `None` if this diagnostic has no line information (e.g. it
+    /// wraps a [`crate::config::ConfigError`] produced after parsing, which
+    /// has no source position at all).
+    pub fn snippet(&self, source: &str) -> Option<String> {
+        let line = self.line?;
+        let text = source.lines().nth(line.checked_sub(1)?)?;
+        let col = self.col.unwrap_or(1).max(1);
+        let width = self
+            .span
+            .as_ref()
+            .map(|span| span.end.saturating_sub(span.start).max(1))
+            .unwrap_or(1);
+        let gutter = line.to_string().len();
+        Some(format!(
+            "{line} | {text}\n{:gutter$} | {}{}",
+            "",
+            " ".repeat(col - 1),
+            "^".repeat(width),
+        ))
+    }
+}
+
+/// Renders a one-line, caret-underlined snippet of `source` pointing at
+/// `span`, without the `line |` gutter [`ParseDiagnostic::snippet`] adds:
+/// for a single-line `source` (e.g. a template literal rather than a whole
+/// JSON/TOML document), resolving a line/column pair first would be
+/// needless overhead. Shared by
+/// [`crate::parser::TemplateParseError::snippet`] and
+/// [`crate::variable::ResolveError::snippet`].
+pub(crate) fn caret_snippet(source: &str, span: &Range<usize>) -> String {
+    let width = span.end.saturating_sub(span.start).max(1);
+    format!("{source}\n{}{}", " ".repeat(span.start), "^".repeat(width))
+}
+
+/// Converts a 0-based byte offset into `source` to a 1-based (line, column)
+/// pair, matching the convention `serde_json::Error::line`/`column` use.
+pub(crate) fn line_col_from_offset(source: &str, offset: usize) -> (usize, usize) {
+    let prefix = &source[..offset.min(source.len())];
+    let line = prefix.matches('\n').count() + 1;
+    let col = prefix
+        .rsplit('\n')
+        .next()
+        .map_or(1, |s| s.chars().count() + 1);
+    (line, col)
+}
+
+/// Extracts the single backtick-quoted name immediately following `prefix`,
+/// given the fixed English phrasing `serde`/`serde_json` generate (e.g.
+/// `"missing field `foo`"`).
+fn extract_quoted_name(message: &str, prefix: &str) -> Option<String> {
+    let rest = message.strip_prefix(prefix)?;
+    let rest = rest.strip_prefix('`')?;
+    let end = rest.find('`')?;
+    Some(rest[..end].to_string())
+}
+
+fn classify_message(message: &str) -> (DiagnosticKind, Option<String>) {
+    if let Some(name) = extract_quoted_name(message, "missing field ") {
+        return (DiagnosticKind::MissingField, Some(name));
+    }
+    if let Some(name) = extract_quoted_name(message, "unknown field ") {
+        return (DiagnosticKind::UnknownField, Some(name));
+    }
+    if let Some(name) = extract_quoted_name(message, "unknown variant ") {
+        return (DiagnosticKind::UnknownVariant, Some(name));
+    }
+    if message.starts_with("invalid length 0") {
+        return (DiagnosticKind::EmptyCollection, None);
+    }
+    (DiagnosticKind::Other, None)
+}
+
+/// Accepted variant names parsed out of serde's `"expected one of \`a\`,
+/// \`b\`"` suffix, used for `unknown_field`/`unknown_variant` diagnostics.
+fn extract_expected(message: &str) -> Option<Vec<String>> {
+    let (_, list) = message.split_once("expected one of ")?;
+    // Drop the trailing "at line X column Y" that serde_json appends.
+    let list = list.split(" at line").next().unwrap_or(list);
+    let names: Vec<String> = list
+        .split(',')
+        .filter_map(|part| {
+            let part = part.trim().strip_prefix('`')?;
+            let part = part.strip_suffix('`')?;
+            Some(part.to_string())
+        })
+        .collect();
+    (!names.is_empty()).then_some(names)
+}
+
+impl From<&serde_json::Error> for ParseDiagnostic {
+    fn from(error: &serde_json::Error) -> Self {
+        let message = error.to_string();
+        let (mut kind, path) = classify_message(&message);
+        if matches!(
+            error.classify(),
+            serde_json::error::Category::Syntax | serde_json::error::Category::Eof
+        ) {
+            kind = DiagnosticKind::Syntax;
+        }
+
+        Self {
+            kind,
+            expected: extract_expected(&message),
+            message,
+            path,
+            line: Some(error.line()),
+            col: Some(error.column()),
+            span: None,
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<&toml::de::Error> for ParseDiagnostic {
+    fn from(error: &toml::de::Error) -> Self {
+        // `toml::de::Error`'s `Display` already inlines its own
+        // caret-underlined snippet into the message, but only exposes the
+        // failure's location as a byte span: resolving that span to a
+        // line/column pair requires the original source text, which isn't
+        // available here. See [`crate::config::ParseTomlError::diagnostic_with_source`].
+        let message = error.to_string();
+        let (kind, path) = classify_message(&message);
+
+        Self {
+            kind,
+            expected: extract_expected(&message),
+            message,
+            path,
+            line: None,
+            col: None,
+            span: error.span(),
+        }
+    }
+}
+
+/// Severity of a [`Diagnostic`] entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The configuration is still usable, but something was ignored or
+    /// could be tightened (e.g. an individual rule was dropped).
+    Warning,
+    /// The operation that produced this diagnostic failed outright.
+    Error,
+}
+
+/// A single structured entry accumulated in a [`Diagnostics`] buffer.
+///
+/// Unlike [`ParseDiagnostic`], which is specific to locating a failure
+/// inside a JSON/TOML document, this is the general-purpose shape used for
+/// anything worth reporting to a caller that only gets an `-errno` back
+/// across the C FFI: a stable machine-readable `code`, the offending
+/// path/port/access-right rendered as free text in `subject` (its shape
+/// varies by `code`), and a human-readable `message`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub subject: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: [{}] ", self.severity, self.code)?;
+        if let Some(subject) = &self.subject {
+            write!(f, "{subject}: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// An ordered, append-only buffer of [`Diagnostic`] entries.
+///
+/// Passed by reference into the `_with_diagnostics` family of
+/// [`crate::config::Config`]/[`crate::config::ResolvedConfig`] methods so
+/// callers can recover warnings alongside a hard error, or inspect why
+/// individual rules were dropped, rather than only getting a single
+/// `anyhow`/`thiserror` error. Mirrored across the C FFI as the opaque
+/// `landlockconfig_diagnostics` handle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.0.push(diagnostic);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_error() {
+        let err = serde_json::from_str::<serde_json::Value>("not json").unwrap_err();
+        let diagnostic = ParseDiagnostic::from(&err);
+        assert_eq!(diagnostic.kind, DiagnosticKind::Syntax);
+    }
+
+    #[test]
+    fn test_extract_missing_field_name() {
+        let (kind, path) = classify_message("missing field `parent` at line 3 column 1");
+        assert_eq!(kind, DiagnosticKind::MissingField);
+        assert_eq!(path.as_deref(), Some("parent"));
+    }
+
+    #[test]
+    fn test_extract_unknown_variant_and_expected() {
+        let message = "unknown variant `foo`, expected one of `a`, `b`, `c` at line 1 column 2";
+        let (kind, path) = classify_message(message);
+        assert_eq!(kind, DiagnosticKind::UnknownVariant);
+        assert_eq!(path.as_deref(), Some("foo"));
+        assert_eq!(
+            extract_expected(message),
+            Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let diagnostic = ParseDiagnostic {
+            kind: DiagnosticKind::MissingField,
+            message: "missing field `parent`".to_string(),
+            path: Some("parent".to_string()),
+            line: Some(3),
+            col: Some(1),
+            expected: None,
+            span: None,
+        };
+        assert_eq!(
+            diagnostic.to_string(),
+            "3:1: parent: missing field `parent`"
+        );
+    }
+
+    #[test]
+    fn test_line_col_from_offset() {
+        let source = "abi: 1\nruleset: []\n";
+        assert_eq!(line_col_from_offset(source, 0), (1, 1));
+        assert_eq!(line_col_from_offset(source, 7), (2, 1));
+        assert_eq!(line_col_from_offset(source, 10), (2, 4));
+    }
+
+    #[test]
+    fn test_snippet() {
+        let diagnostic = ParseDiagnostic {
+            kind: DiagnosticKind::Other,
+            message: "invalid value".to_string(),
+            path: None,
+            line: Some(2),
+            col: Some(4),
+            expected: None,
+            span: Some(9..11),
+        };
+        let source = "abi: 1\nabi: 0\n";
+        assert_eq!(
+            diagnostic.snippet(source),
+            Some("2 | abi: 0\n  |    ^^".to_string())
+        );
+    }
+
+    #[test]
+    fn test_caret_snippet() {
+        assert_eq!(
+            caret_snippet("${bar}", &(0..6)),
+            "${bar}\n^^^^^^".to_string()
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_error_has_span() {
+        let err = toml::from_str::<toml::Value>("abi = ").unwrap_err();
+        let diagnostic = ParseDiagnostic::from(&err);
+        assert!(diagnostic.span.is_some());
+    }
+}
@@ -1,16 +1,22 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-pub use config::{BuildRulesetError, Config, ConfigFormat, OptionalConfig, RuleError};
+pub use config::{
+    detected_abi, AbiCompatReport, AbiDowngradeReport, AbiRequirement, BuildRulesetError,
+    CheckReport, CompatLevel, CompatibilityReport, ComposeMode, Config, ConfigFormat, DroppedRight,
+    OptionalConfig, PathCheckIssue, RuleError, Source, ValidationError, VariableSource,
+};
+pub use diagnostic::{Diagnostic, DiagnosticKind, Diagnostics, ParseDiagnostic, Severity};
+pub use oci::ParseOciError;
+pub use schema::{schema, validate_config, LandlockConfigError};
 
 mod config;
+mod diagnostic;
 mod nonempty;
+mod oci;
 mod parser;
+mod schema;
 mod variable;
 
-#[cfg(test)]
-#[macro_use]
-extern crate lazy_static;
-
 #[cfg(test)]
 mod tests_helpers;
 
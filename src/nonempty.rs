@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::BTreeSet;
 use std::iter::FromIterator;
 use std::ops::Deref;
@@ -32,6 +32,18 @@ where
     }
 }
 
+impl<T> Serialize for NonEmptySet<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
 impl<T> Deref for NonEmptySet<T> {
     type Target = BTreeSet<T>;
 
@@ -73,6 +85,15 @@ impl<T> NonEmptyStruct<T>
 where
     T: NonEmptyStructInner,
 {
+    /// Builds a `NonEmptyStruct` for serialization purposes.
+    ///
+    /// Callers must ensure `inner` is not empty; unlike deserialization, this
+    /// is not re-checked here.
+    pub(crate) fn new(inner: T) -> Self {
+        debug_assert!(!inner.is_empty());
+        Self(inner)
+    }
+
     pub(crate) fn into_inner(self) -> T {
         self.0
     }
@@ -85,6 +106,18 @@ where
     }
 }
 
+impl<T> Serialize for NonEmptyStruct<T>
+where
+    T: NonEmptyStructInner + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
 impl<'de, T> Deserialize<'de> for NonEmptyStruct<T>
 where
     T: Deserialize<'de> + NonEmptyStructInner,
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Ingests the `mounts` array of an OCI runtime-spec `config.json` as
+//! Landlock `pathBeneath` rules.
+//!
+//! This only looks at the handful of fields landlockconfig cares about
+//! (`mounts[].destination` and `mounts[].options`); every other field of the
+//! runtime spec (`process`, `root`, `linux`, `hooks`, ...) is accepted and
+//! ignored, so a full bundle `config.json` can be fed in directly instead of
+//! requiring callers to pre-filter it down to just the `mounts` array.
+
+use crate::config::Config;
+use crate::diagnostic::{Diagnostic, Diagnostics, Severity};
+use crate::parser::{TemplateString, TemplateToken};
+use landlock::{AccessFs, BitFlags};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// A single entry of the OCI runtime-spec `mounts` array.
+///
+/// Deserializes leniently: any field other than `destination` and `options`
+/// (e.g. `source`, `type`, `uidMappings`) is accepted and ignored, since
+/// this struct only exists to extract a Landlock rule out of it.
+#[derive(Debug, Deserialize)]
+struct OciMount {
+    destination: String,
+    #[serde(default)]
+    options: Vec<String>,
+}
+
+/// The subset of an OCI runtime-spec `config.json` landlockconfig reads.
+///
+/// `#[serde(default)]` on `mounts` and the lack of `deny_unknown_fields`
+/// mean every other top-level field (`ociVersion`, `process`, `root`,
+/// `linux`, ...) is silently accepted: deserialization only fails if
+/// `mounts` itself is present but malformed.
+#[derive(Debug, Deserialize)]
+struct OciSpec {
+    #[serde(default)]
+    mounts: Vec<OciMount>,
+}
+
+#[derive(Debug, Error)]
+pub enum ParseOciError {
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+impl From<&ParseOciError> for Diagnostic {
+    fn from(err: &ParseOciError) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code: "parse_oci_error",
+            subject: None,
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Maps an OCI mount's `options` array to the `AccessFs` rights Landlock
+/// should allow on its `destination`.
+///
+/// `ro` restricts to read-only access; any other value, including the
+/// runtime spec's own default of no `options` at all, is treated as
+/// read-write (`rw` is simply the explicit spelling of that default). The
+/// absence of `noexec` additionally allows `Execute`. `nosuid`/`bind` and
+/// any other mount option have no Landlock equivalent and are ignored.
+fn access_for_options(options: &[String]) -> BitFlags<AccessFs> {
+    let mut access: BitFlags<AccessFs> = if options.iter().any(|option| option == "ro") {
+        AccessFs::ReadFile | AccessFs::ReadDir
+    } else {
+        AccessFs::ReadFile
+            | AccessFs::ReadDir
+            | AccessFs::WriteFile
+            | AccessFs::RemoveDir
+            | AccessFs::RemoveFile
+            | AccessFs::MakeChar
+            | AccessFs::MakeDir
+            | AccessFs::MakeReg
+            | AccessFs::MakeSock
+            | AccessFs::MakeFifo
+            | AccessFs::MakeBlock
+            | AccessFs::MakeSym
+    };
+
+    if !options.iter().any(|option| option == "noexec") {
+        access |= AccessFs::Execute;
+    }
+
+    access
+}
+
+impl Config {
+    /// Ingests the `mounts` array of an OCI runtime-spec `config.json` (as
+    /// produced by `runc spec` or found in a container image bundle) as
+    /// `pathBeneath` rules: each mount's `destination` becomes a `parent`,
+    /// and its `options` are mapped to an `AccessFs` set, see
+    /// [`access_for_options`].
+    ///
+    /// The result composes with hand-written configs exactly like any other
+    /// `Config`, so a container runtime can derive a Landlock sandbox
+    /// straight from the bundle it already has.
+    pub fn parse_oci<R>(reader: R) -> Result<Self, ParseOciError>
+    where
+        R: std::io::Read,
+    {
+        let spec: OciSpec = serde_json::from_reader(reader)?;
+        let mut config = Self::empty();
+
+        for mount in spec.mounts {
+            let access = access_for_options(&mount.options);
+            config.handled_fs |= access;
+            let parent = TemplateString(vec![TemplateToken::Text(mount.destination)]);
+            config
+                .rules_path_beneath
+                .entry(parent)
+                .and_modify(|a| *a |= access)
+                .or_insert(access);
+        }
+
+        Ok(config)
+    }
+
+    /// Like [`Config::parse_oci`], but also pushes a [`Diagnostic`]
+    /// describing the failure into `diagnostics` on error. See
+    /// [`Config::parse_json_with_diagnostics`].
+    pub fn parse_oci_with_diagnostics<R>(
+        reader: R,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Self, ParseOciError>
+    where
+        R: std::io::Read,
+    {
+        let result = Self::parse_oci(reader);
+        if let Err(e) = &result {
+            diagnostics.push(e.into());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_oci_maps_mounts_to_path_beneath() {
+        let data = r#"{
+            "ociVersion": "1.0.2",
+            "process": { "terminal": true },
+            "mounts": [
+                { "destination": "/proc", "type": "proc", "source": "proc" },
+                {
+                    "destination": "/sys",
+                    "type": "sysfs",
+                    "source": "sysfs",
+                    "options": ["nosuid", "noexec", "nodev", "ro"]
+                }
+            ]
+        }"#;
+
+        let config = Config::parse_oci(data.as_bytes()).unwrap();
+        let proc_parent = TemplateString::from_text("/proc");
+        assert_eq!(
+            config.rules_path_beneath.get(&proc_parent),
+            Some(
+                &(AccessFs::ReadFile
+                    | AccessFs::ReadDir
+                    | AccessFs::WriteFile
+                    | AccessFs::RemoveDir
+                    | AccessFs::RemoveFile
+                    | AccessFs::MakeChar
+                    | AccessFs::MakeDir
+                    | AccessFs::MakeReg
+                    | AccessFs::MakeSock
+                    | AccessFs::MakeFifo
+                    | AccessFs::MakeBlock
+                    | AccessFs::MakeSym
+                    | AccessFs::Execute)
+            )
+        );
+        let sys_parent = TemplateString::from_text("/sys");
+        assert_eq!(
+            config.rules_path_beneath.get(&sys_parent),
+            Some(&(AccessFs::ReadFile | AccessFs::ReadDir))
+        );
+    }
+
+    #[test]
+    fn test_parse_oci_ignores_unknown_top_level_fields() {
+        let data = r#"{
+            "ociVersion": "1.0.2",
+            "linux": { "namespaces": [ { "type": "pid" } ] }
+        }"#;
+
+        let config = Config::parse_oci(data.as_bytes()).unwrap();
+        assert!(config.rules_path_beneath.is_empty());
+    }
+
+    #[test]
+    fn test_parse_oci_with_diagnostics_reports_error() {
+        let mut diagnostics = Diagnostics::new();
+        let err = Config::parse_oci_with_diagnostics("not json".as_bytes(), &mut diagnostics)
+            .unwrap_err();
+        assert!(matches!(err, ParseOciError::SerdeJson(_)));
+        assert_eq!(diagnostics.len(), 1);
+    }
+}
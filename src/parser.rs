@@ -1,18 +1,102 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use crate::{
+    diagnostic::caret_snippet,
     nonempty::{NonEmptySet, NonEmptyStruct, NonEmptyStructInner},
     variable::{Name, ResolveError},
 };
 use landlock::{Access, AccessFs, AccessNet, BitFlags, Scope, ABI};
-use serde::de::{Unexpected, Visitor};
+use serde::de::{MapAccess, Unexpected, Visitor};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeSet;
+use std::ops::Range;
 use std::str::FromStr;
+use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TemplateToken {
     Text(String),
-    Var(Name),
+    Var {
+        name: Name,
+        /// `${name:-default}`: the value to use when `name` is unset,
+        /// itself a template so a default may reference other variables.
+        default: Option<Box<TemplateString>>,
+        /// `${name:?message}`: fail resolution with this message when
+        /// `name` is unset.
+        required_msg: Option<String>,
+        /// `${name:+alt}`: the value to use in place of `name`'s own value
+        /// when `name` *is* set, itself a template so it may reference
+        /// other variables. When `name` is unset, resolves to an empty set
+        /// rather than an error, mirroring POSIX shell's `${var:+alt}`
+        /// expansion.
+        alt: Option<Box<TemplateString>>,
+        /// Byte offsets of this whole `${...}` reference within the
+        /// template literal it was parsed from, `(start, end)` rather than
+        /// [`Range`] so the field keeps deriving `Ord`/`Hash` for
+        /// [`TemplateString`]'s use as a `BTreeMap` key. Surfaced by
+        /// [`crate::variable::ResolveError::VariableNotFound`] so a caller
+        /// can point a diagnostic at exactly the undefined reference.
+        span: (usize, usize),
+    },
+    /// A `${env:NAME}` reference, resolved against the process environment
+    /// instead of the `variable` section, see
+    /// [`crate::variable::Variables::resolve`].
+    Env {
+        name: Name,
+        /// `${env:NAME:-default}`: the value to use when the `NAME`
+        /// environment variable is unset, itself a template so a default
+        /// may reference other variables/env vars.
+        default: Option<Box<TemplateString>>,
+        /// See the `span` field on [`TemplateToken::Var`].
+        span: (usize, usize),
+    },
+    /// `${join(a, b, ...)}`: concatenates each argument's expansion with a
+    /// single `/` between them, collapsing a doubled separator rather than
+    /// producing `a//b`. Only meaningful to
+    /// [`crate::variable::TemplateString::expand`]; at least two arguments
+    /// are required at parse time.
+    Join {
+        args: Vec<TemplateString>,
+        /// See the `span` field on [`TemplateToken::Var`].
+        span: (usize, usize),
+    },
+    /// `${regex_replace(src, pattern, replacement)}`: replaces `pattern` in
+    /// `src`'s expansion with `replacement`. Only meaningful to
+    /// [`crate::variable::TemplateString::expand`]; `pattern` is matched as
+    /// a plain substring rather than a full regular expression, since this
+    /// crate doesn't otherwise depend on a regex engine.
+    RegexReplace {
+        src: Box<TemplateString>,
+        pattern: Box<TemplateString>,
+        replacement: Box<TemplateString>,
+        /// See the `span` field on [`TemplateToken::Var`].
+        span: (usize, usize),
+    },
+}
+
+impl TemplateToken {
+    pub(crate) fn var(name: Name, span: (usize, usize)) -> Self {
+        Self::Var {
+            name,
+            default: None,
+            required_msg: None,
+            alt: None,
+            span,
+        }
+    }
+
+    /// Byte range of this token's `${...}` reference as a [`Range`], for
+    /// callers that want the more idiomatic type than the `(start, end)`
+    /// tuple stored on [`TemplateToken::Var`]/[`TemplateToken::Env`].
+    pub(crate) fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::Var { span, .. }
+            | Self::Env { span, .. }
+            | Self::Join { span, .. }
+            | Self::RegexReplace { span, .. } => Some(span.0..span.1),
+            Self::Text(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -33,7 +117,56 @@ impl std::fmt::Display for TemplateString {
         for token in &self.0 {
             match token {
                 TemplateToken::Text(text) => f.write_str(text)?,
-                TemplateToken::Var(var) => write!(f, "${{{}}}", var)?,
+                TemplateToken::Var {
+                    name,
+                    default,
+                    required_msg,
+                    alt,
+                    span: _,
+                } => {
+                    write!(f, "${{{}", name)?;
+                    if let Some(default) = default {
+                        write!(f, ":-{}", default)?;
+                    } else if let Some(msg) = required_msg {
+                        write!(f, ":?{}", msg)?;
+                    } else if let Some(alt) = alt {
+                        write!(f, ":+{}", alt)?;
+                    }
+                    write!(f, "}}")?;
+                }
+                TemplateToken::Env {
+                    name,
+                    default,
+                    span: _,
+                } => {
+                    write!(f, "${{env:{}", name)?;
+                    if let Some(default) = default {
+                        write!(f, ":-{}", default)?;
+                    }
+                    write!(f, "}}")?;
+                }
+                TemplateToken::Join { args, span: _ } => {
+                    write!(f, "${{join(")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", arg)?;
+                    }
+                    write!(f, ")}}")?;
+                }
+                TemplateToken::RegexReplace {
+                    src,
+                    pattern,
+                    replacement,
+                    span: _,
+                } => {
+                    write!(
+                        f,
+                        "${{regex_replace({}, {}, {})}}",
+                        src, pattern, replacement
+                    )?;
+                }
             }
         }
         Ok(())
@@ -54,21 +187,141 @@ enum TemplateState {
     Text(usize),
     FirstDollar(usize),
     Variable(usize),
+    /// Seen the `:` following a variable name; `colon_pos` decides whether
+    /// it introduces a `-default`, `?message` or `+alt` suffix once the next
+    /// character is known.
+    Colon {
+        name_start: usize,
+        colon_pos: usize,
+    },
+    /// Collecting a `:-default` value, tracking nested `${...}` brace depth
+    /// so the default may itself reference other variables.
+    Default {
+        name_start: usize,
+        colon_pos: usize,
+        value_start: usize,
+        depth: u32,
+        after_dollar: bool,
+    },
+    /// Collecting a `:?message` value.
+    Required {
+        name_start: usize,
+        colon_pos: usize,
+        msg_start: usize,
+    },
+    /// Collecting a `:+alt` value, tracking nested `${...}` brace depth so
+    /// the alternate value may itself reference other variables.
+    Alt {
+        name_start: usize,
+        colon_pos: usize,
+        value_start: usize,
+        depth: u32,
+        after_dollar: bool,
+    },
+    /// Collecting the comma-separated arguments of a `${name(...)}` call
+    /// (e.g. `${join(a, b)}`), tracking nested `${...}` brace depth per
+    /// argument the same way [`TemplateState::Default`] does, since an
+    /// argument is itself a template. A top-level (`depth == 0`) comma
+    /// closes the current argument; a top-level `)` closes the whole call.
+    Call {
+        name_start: usize,
+        paren_pos: usize,
+        arg_start: usize,
+        args: Vec<(usize, usize)>,
+        depth: u32,
+        after_dollar: bool,
+    },
+    /// Seen a call's closing `)`; only a `}` may follow.
+    CallClose {
+        name_start: usize,
+        paren_pos: usize,
+        args: Vec<(usize, usize)>,
+    },
 }
 
-struct TemplateStringVisitor;
+/// The parsed head of a `${...}` reference once its `env:` prefix (if any)
+/// has been stripped off, used where a `:-default` suffix must build either
+/// a [`TemplateToken::Var`] or a [`TemplateToken::Env`] depending on which
+/// namespace it belongs to.
+enum TemplateHead {
+    Var(Name),
+    Env(Name),
+}
 
-impl<'de> de::Visitor<'de> for TemplateStringVisitor {
-    type Value = TemplateString;
+/// Structured error from [`TemplateString::tokenize`], carrying the byte
+/// offset of the failure within whatever string was being tokenized (the
+/// full template literal, or the substring of a nested `:-default`) rather
+/// than collapsing straight into an opaque `serde` "data" error. Used both
+/// to build [`TemplateStringVisitor`]'s `E::custom` message and, via
+/// [`TemplateParseError::offset`], to let diagnostics point a caret at
+/// exactly the failing `${`/`}`/character.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub(crate) enum TemplateParseError {
+    #[error("empty environment variable name at position {offset}")]
+    EmptyEnvVarName { offset: usize },
+    #[error("invalid variable name at position {offset}: {source}")]
+    InvalidName {
+        offset: usize,
+        #[source]
+        source: NameError,
+    },
+    #[error("unclosed variable reference starting at position {offset}")]
+    Unclosed { offset: usize },
+    /// An unknown function name, wrong argument count, or a call missing
+    /// its closing `}`, in a `${name(...)}` reference.
+    #[error("invalid call at position {offset}: {message}")]
+    InvalidCall { offset: usize, message: String },
+}
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("a string with optional variable references like ${var}")
+impl TemplateParseError {
+    pub(crate) fn offset(&self) -> usize {
+        match self {
+            Self::EmptyEnvVarName { offset }
+            | Self::InvalidName { offset, .. }
+            | Self::Unclosed { offset }
+            | Self::InvalidCall { offset, .. } => *offset,
+        }
     }
 
-    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
+    /// Renders a one-line, caret-underlined snippet of `source` (the
+    /// template literal this error was produced from) pointing at
+    /// [`TemplateParseError::offset`].
+    pub(crate) fn snippet(&self, source: &str) -> String {
+        caret_snippet(source, &(self.offset()..self.offset() + 1))
+    }
+
+    /// Shifts this error's offset by `delta`, used when a nested
+    /// `:-default`/`:+alt` value (tokenized against its own zero-based
+    /// substring) bubbles an error up into the coordinates of the outer
+    /// template.
+    fn shifted(self, delta: usize) -> Self {
+        match self {
+            Self::EmptyEnvVarName { offset } => Self::EmptyEnvVarName {
+                offset: offset + delta,
+            },
+            Self::InvalidName { offset, source } => Self::InvalidName {
+                offset: offset + delta,
+                source,
+            },
+            Self::Unclosed { offset } => Self::Unclosed {
+                offset: offset + delta,
+            },
+            Self::InvalidCall { offset, message } => Self::InvalidCall {
+                offset: offset + delta,
+                message,
+            },
+        }
+    }
+}
+
+impl TemplateString {
+    /// Tokenizes `value` into the `Text`/`Var`/`Env`/`Join`/`RegexReplace`
+    /// tokens making up a [`TemplateString`], threading an offset counter through the scan so
+    /// a failure (unterminated `${`, empty `${}`, invalid name character)
+    /// can report exactly where it occurred instead of only a generic
+    /// `serde` data error. [`TemplateStringVisitor::visit_str`] is a thin
+    /// wrapper over this for the `Deserialize` impl.
+    pub(crate) fn tokenize(value: &str) -> Result<Self, TemplateParseError> {
         let mut tokens = Vec::new();
         let mut state = TemplateState::Text(0);
 
@@ -82,6 +335,135 @@ impl<'de> de::Visitor<'de> for TemplateStringVisitor {
             }
         };
 
+        // Parses the body of a plain `${...}` reference with no `:-`/`:?`/`:+`
+        // suffix: either a `env:NAME` environment-variable reference or a
+        // variable name.
+        let parse_plain_var = |body: &str,
+                               name_start: usize,
+                               span: (usize, usize)|
+         -> Result<TemplateToken, TemplateParseError> {
+            if let Some(env_name) = body.strip_prefix("env:") {
+                if env_name.is_empty() {
+                    return Err(TemplateParseError::EmptyEnvVarName {
+                        offset: name_start - 2,
+                    });
+                }
+                let name =
+                    Name::from_str(env_name).map_err(|source| TemplateParseError::InvalidName {
+                        offset: name_start - 2,
+                        source,
+                    })?;
+                Ok(TemplateToken::Env {
+                    name,
+                    default: None,
+                    span,
+                })
+            } else {
+                let name =
+                    Name::from_str(body).map_err(|source| TemplateParseError::InvalidName {
+                        offset: name_start - 2,
+                        source,
+                    })?;
+                Ok(TemplateToken::var(name, span))
+            }
+        };
+
+        // Parses the arguments collected by `TemplateState::Call`/`CallClose`
+        // (each a byte range of the original `value`, already split on
+        // top-level commas) into a `join`/`regex_replace` token, erroring on
+        // an unknown function name or wrong argument count.
+        let parse_call = |name_start: usize,
+                          paren_pos: usize,
+                          args: &[(usize, usize)],
+                          span: (usize, usize)|
+         -> Result<TemplateToken, TemplateParseError> {
+            let name = &value[name_start..paren_pos];
+            let mut templates = Vec::with_capacity(args.len());
+            for &(start, end) in args {
+                // Arguments are comma-separated, so conventional
+                // `${join(a, b)}` spacing would otherwise tokenize a
+                // leading space into each argument but the first.
+                let raw = &value[start..end];
+                let trimmed = raw.trim();
+                let trim_start = raw.len() - raw.trim_start().len();
+                templates.push(Self::tokenize(trimmed).map_err(|e| e.shifted(start + trim_start))?);
+            }
+            match name {
+                "join" => {
+                    if templates.len() < 2 {
+                        return Err(TemplateParseError::InvalidCall {
+                            offset: name_start - 2,
+                            message: format!(
+                                "join() expects at least 2 arguments, found {}",
+                                templates.len()
+                            ),
+                        });
+                    }
+                    Ok(TemplateToken::Join {
+                        args: templates,
+                        span,
+                    })
+                }
+                "regex_replace" => {
+                    let found = templates.len();
+                    let [src, pattern, replacement]: [TemplateString; 3] = templates
+                        .try_into()
+                        .map_err(|_| TemplateParseError::InvalidCall {
+                            offset: name_start - 2,
+                            message: format!(
+                                "regex_replace() expects exactly 3 arguments, found {found}"
+                            ),
+                        })?;
+                    Ok(TemplateToken::RegexReplace {
+                        src: Box::new(src),
+                        pattern: Box::new(pattern),
+                        replacement: Box::new(replacement),
+                        span,
+                    })
+                }
+                other => Err(TemplateParseError::InvalidCall {
+                    offset: name_start - 2,
+                    message: format!("unknown function `{other}`"),
+                }),
+            }
+        };
+
+        let parse_name = |name_start: usize, name_end: usize| -> Result<Name, TemplateParseError> {
+            Name::from_str(&value[name_start..name_end]).map_err(|source| {
+                TemplateParseError::InvalidName {
+                    offset: name_start - 2,
+                    source,
+                }
+            })
+        };
+
+        // Like `parse_name`, but also recognizes the `env:NAME` prefix so a
+        // `:-default` suffix can be combined with either a `variable`
+        // reference or an environment-variable one, e.g.
+        // `${env:HOME:-/tmp}`. `:?message`/`:+alt` don't support the `env:`
+        // prefix; combining them is rejected the same way an invalid name
+        // character would be.
+        let parse_var_or_env_name = |name_start: usize,
+                                     name_end: usize|
+         -> Result<TemplateHead, TemplateParseError> {
+            let raw = &value[name_start..name_end];
+            if let Some(env_name) = raw.strip_prefix("env:") {
+                if env_name.is_empty() {
+                    return Err(TemplateParseError::EmptyEnvVarName {
+                        offset: name_start - 2,
+                    });
+                }
+                let name =
+                    Name::from_str(env_name).map_err(|source| TemplateParseError::InvalidName {
+                        offset: name_start - 2,
+                        source,
+                    })?;
+                Ok(TemplateHead::Env(name))
+            } else {
+                Ok(TemplateHead::Var(parse_name(name_start, name_end)?))
+            }
+        };
+
         for (i, c) in value.char_indices() {
             state = match state {
                 TemplateState::Text(text_start) => match c {
@@ -105,20 +487,274 @@ impl<'de> de::Visitor<'de> for TemplateStringVisitor {
                     }
                 },
                 TemplateState::Variable(name_start) => match c {
+                    ':' => TemplateState::Colon {
+                        name_start,
+                        colon_pos: i,
+                    },
+                    '(' => TemplateState::Call {
+                        name_start,
+                        paren_pos: i,
+                        arg_start: i + 1,
+                        args: Vec::new(),
+                        depth: 0,
+                        after_dollar: false,
+                    },
                     '}' => {
-                        // Get the variable name
-                        let name = Name::from_str(&value[name_start..i]).map_err(|e| {
-                            E::custom(format!(
-                                "invalid variable name at position {}: {}",
-                                name_start - 2,
-                                e
-                            ))
-                        })?;
-                        tokens.push(TemplateToken::Var(name));
+                        // Get the variable name (or `env:NAME` for an
+                        // environment-variable reference).
+                        let token = parse_plain_var(
+                            &value[name_start..i],
+                            name_start,
+                            (name_start - 2, i + 1),
+                        )?;
+                        tokens.push(token);
+                        TemplateState::Text(i + 1)
+                    }
+                    _ => TemplateState::Variable(name_start),
+                },
+                TemplateState::Colon {
+                    name_start,
+                    colon_pos,
+                } => match c {
+                    '-' => TemplateState::Default {
+                        name_start,
+                        colon_pos,
+                        value_start: i + 1,
+                        depth: 1,
+                        after_dollar: false,
+                    },
+                    '?' => TemplateState::Required {
+                        name_start,
+                        colon_pos,
+                        msg_start: i + 1,
+                    },
+                    '+' => TemplateState::Alt {
+                        name_start,
+                        colon_pos,
+                        value_start: i + 1,
+                        depth: 1,
+                        after_dollar: false,
+                    },
+                    '}' => {
+                        // `:` was just part of an (invalid) variable name.
+                        let token = parse_plain_var(
+                            &value[name_start..i],
+                            name_start,
+                            (name_start - 2, i + 1),
+                        )?;
+                        tokens.push(token);
                         TemplateState::Text(i + 1)
                     }
                     _ => TemplateState::Variable(name_start),
                 },
+                TemplateState::Default {
+                    name_start,
+                    colon_pos,
+                    value_start,
+                    depth,
+                    after_dollar,
+                } => match c {
+                    '$' => TemplateState::Default {
+                        name_start,
+                        colon_pos,
+                        value_start,
+                        depth,
+                        after_dollar: true,
+                    },
+                    '{' if after_dollar => TemplateState::Default {
+                        name_start,
+                        colon_pos,
+                        value_start,
+                        depth: depth + 1,
+                        after_dollar: false,
+                    },
+                    '}' if depth > 1 => TemplateState::Default {
+                        name_start,
+                        colon_pos,
+                        value_start,
+                        depth: depth - 1,
+                        after_dollar: false,
+                    },
+                    '}' => {
+                        let head = parse_var_or_env_name(name_start, colon_pos)?;
+                        let default = Self::tokenize(&value[value_start..i])
+                            .map_err(|e| e.shifted(value_start))
+                            .map(Box::new)?;
+                        let span = (name_start - 2, i + 1);
+                        tokens.push(match head {
+                            TemplateHead::Var(name) => TemplateToken::Var {
+                                name,
+                                default: Some(default),
+                                required_msg: None,
+                                alt: None,
+                                span,
+                            },
+                            TemplateHead::Env(name) => TemplateToken::Env {
+                                name,
+                                default: Some(default),
+                                span,
+                            },
+                        });
+                        TemplateState::Text(i + 1)
+                    }
+                    _ => TemplateState::Default {
+                        name_start,
+                        colon_pos,
+                        value_start,
+                        depth,
+                        after_dollar: false,
+                    },
+                },
+                TemplateState::Required {
+                    name_start,
+                    colon_pos,
+                    msg_start,
+                } => match c {
+                    '}' => {
+                        let name = parse_name(name_start, colon_pos)?;
+                        tokens.push(TemplateToken::Var {
+                            name,
+                            default: None,
+                            required_msg: Some(value[msg_start..i].to_string()),
+                            alt: None,
+                            span: (name_start - 2, i + 1),
+                        });
+                        TemplateState::Text(i + 1)
+                    }
+                    _ => TemplateState::Required {
+                        name_start,
+                        colon_pos,
+                        msg_start,
+                    },
+                },
+                TemplateState::Alt {
+                    name_start,
+                    colon_pos,
+                    value_start,
+                    depth,
+                    after_dollar,
+                } => match c {
+                    '$' => TemplateState::Alt {
+                        name_start,
+                        colon_pos,
+                        value_start,
+                        depth,
+                        after_dollar: true,
+                    },
+                    '{' if after_dollar => TemplateState::Alt {
+                        name_start,
+                        colon_pos,
+                        value_start,
+                        depth: depth + 1,
+                        after_dollar: false,
+                    },
+                    '}' if depth > 1 => TemplateState::Alt {
+                        name_start,
+                        colon_pos,
+                        value_start,
+                        depth: depth - 1,
+                        after_dollar: false,
+                    },
+                    '}' => {
+                        let name = parse_name(name_start, colon_pos)?;
+                        let alt = Self::tokenize(&value[value_start..i])
+                            .map_err(|e| e.shifted(value_start))
+                            .map(Box::new)?;
+                        tokens.push(TemplateToken::Var {
+                            name,
+                            default: None,
+                            required_msg: None,
+                            alt: Some(alt),
+                            span: (name_start - 2, i + 1),
+                        });
+                        TemplateState::Text(i + 1)
+                    }
+                    _ => TemplateState::Alt {
+                        name_start,
+                        colon_pos,
+                        value_start,
+                        depth,
+                        after_dollar: false,
+                    },
+                },
+                TemplateState::Call {
+                    name_start,
+                    paren_pos,
+                    arg_start,
+                    mut args,
+                    depth,
+                    after_dollar,
+                } => match c {
+                    '$' => TemplateState::Call {
+                        name_start,
+                        paren_pos,
+                        arg_start,
+                        args,
+                        depth,
+                        after_dollar: true,
+                    },
+                    '{' if after_dollar => TemplateState::Call {
+                        name_start,
+                        paren_pos,
+                        arg_start,
+                        args,
+                        depth: depth + 1,
+                        after_dollar: false,
+                    },
+                    '}' if depth > 0 => TemplateState::Call {
+                        name_start,
+                        paren_pos,
+                        arg_start,
+                        args,
+                        depth: depth - 1,
+                        after_dollar: false,
+                    },
+                    ',' if depth == 0 => {
+                        args.push((arg_start, i));
+                        TemplateState::Call {
+                            name_start,
+                            paren_pos,
+                            arg_start: i + 1,
+                            args,
+                            depth: 0,
+                            after_dollar: false,
+                        }
+                    }
+                    ')' if depth == 0 => {
+                        args.push((arg_start, i));
+                        TemplateState::CallClose {
+                            name_start,
+                            paren_pos,
+                            args,
+                        }
+                    }
+                    _ => TemplateState::Call {
+                        name_start,
+                        paren_pos,
+                        arg_start,
+                        args,
+                        depth,
+                        after_dollar: false,
+                    },
+                },
+                TemplateState::CallClose {
+                    name_start,
+                    paren_pos,
+                    args,
+                } => match c {
+                    '}' => {
+                        let token =
+                            parse_call(name_start, paren_pos, &args, (name_start - 2, i + 1))?;
+                        tokens.push(token);
+                        TemplateState::Text(i + 1)
+                    }
+                    _ => {
+                        return Err(TemplateParseError::InvalidCall {
+                            offset: name_start - 2,
+                            message: "expected '}' after ')'".to_string(),
+                        })
+                    }
+                },
             };
         }
 
@@ -127,11 +763,16 @@ impl<'de> de::Visitor<'de> for TemplateStringVisitor {
                 // Get text up to the second dollar sign
                 push_text(&mut tokens, &value[text_start..]);
             }
-            TemplateState::Variable(name_start) => {
-                return Err(E::custom(format!(
-                    "unclosed variable reference starting at position {}",
-                    name_start - 2
-                )));
+            TemplateState::Variable(name_start)
+            | TemplateState::Colon { name_start, .. }
+            | TemplateState::Default { name_start, .. }
+            | TemplateState::Required { name_start, .. }
+            | TemplateState::Alt { name_start, .. }
+            | TemplateState::Call { name_start, .. }
+            | TemplateState::CallClose { name_start, .. } => {
+                return Err(TemplateParseError::Unclosed {
+                    offset: name_start - 2,
+                });
             }
         }
 
@@ -139,6 +780,23 @@ impl<'de> de::Visitor<'de> for TemplateStringVisitor {
     }
 }
 
+struct TemplateStringVisitor;
+
+impl<'de> de::Visitor<'de> for TemplateStringVisitor {
+    type Value = TemplateString;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a string with optional variable references like ${var}")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        TemplateString::tokenize(value).map_err(|e| E::custom(e.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests_template_string {
     use super::*;
@@ -183,7 +841,10 @@ mod tests_template_string {
             TemplateStringVisitor
                 .visit_str::<TestError>("${foo}")
                 .unwrap(),
-            TemplateString(vec![TemplateToken::Var(Name::from_str("foo").unwrap())])
+            TemplateString(vec![TemplateToken::var(
+                Name::from_str("foo").unwrap(),
+                (0, 6)
+            )])
         );
     }
 
@@ -194,7 +855,7 @@ mod tests_template_string {
                 .visit_str::<TestError>("${foo} bar")
                 .unwrap(),
             TemplateString(vec![
-                TemplateToken::Var(Name::from_str("foo").unwrap()),
+                TemplateToken::var(Name::from_str("foo").unwrap(), (0, 6)),
                 TemplateToken::Text(" bar".to_string()),
             ])
         );
@@ -207,13 +868,55 @@ mod tests_template_string {
                 .visit_str::<TestError>("${foo} bar ${baz}")
                 .unwrap(),
             TemplateString(vec![
-                TemplateToken::Var(Name::from_str("foo").unwrap()),
+                TemplateToken::var(Name::from_str("foo").unwrap(), (0, 6)),
                 TemplateToken::Text(" bar ".to_string()),
-                TemplateToken::Var(Name::from_str("baz").unwrap())
+                TemplateToken::var(Name::from_str("baz").unwrap(), (11, 17))
             ])
         );
     }
 
+    #[test]
+    fn test_visit_str_env_var() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${env:HOME}")
+                .unwrap(),
+            TemplateString(vec![TemplateToken::Env {
+                name: Name::from_str("HOME").unwrap(),
+                default: None,
+                span: (0, 11),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_visit_str_empty_env_var_name() {
+        assert!(TemplateStringVisitor
+            .visit_str::<TestError>("${env:}")
+            .is_err());
+    }
+
+    #[test]
+    fn test_visit_str_env_var_with_default() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${env:HOME:-/default/home}")
+                .unwrap(),
+            TemplateString(vec![TemplateToken::Env {
+                name: Name::from_str("HOME").unwrap(),
+                default: Some(Box::new(TemplateString::from_text("/default/home"))),
+                span: (0, 26),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_visit_str_invalid_env_var_name_with_default() {
+        assert!(TemplateStringVisitor
+            .visit_str::<TestError>("${env:1foo:-/default}")
+            .is_err());
+    }
+
     #[test]
     fn test_visit_str_escaped_variable() {
         assert_eq!(
@@ -262,13 +965,19 @@ mod tests_template_string {
         );
     }
 
+    #[test]
+    fn test_template_parse_error_snippet() {
+        let err = TemplateString::tokenize("${unclosed").unwrap_err();
+        assert_eq!(err.snippet("${unclosed"), "${unclosed\n^");
+    }
+
     #[test]
     fn test_visit_str_invalid_variable_first_char() {
         assert_eq!(TemplateStringVisitor
             .visit_str::<TestError>(" ${0}")
             .unwrap_err()
             .0,
-            "invalid variable name at position 1: invalid first character in name (must be ASCII alphabetic): 0");
+            "invalid variable name at position 1: invalid first character `0` at position 0 (must be ASCII alphabetic)");
     }
 
     #[test]
@@ -277,7 +986,7 @@ mod tests_template_string {
             .visit_str::<TestError>("${invalid-name}")
             .unwrap_err()
             .0,
-            "invalid variable name at position 0: invalid character(s) in name (must be ASCII alphanumeric or '_'): invalid-name");
+            "invalid variable name at position 0: invalid character `-` at position 7 (must be ASCII alphanumeric or '_')");
     }
 
     #[test]
@@ -290,6 +999,208 @@ mod tests_template_string {
             "invalid variable name at position 2: name cannot be empty"
         );
     }
+
+    #[test]
+    fn test_visit_str_variable_with_default() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:-/default/path}")
+                .unwrap(),
+            TemplateString(vec![TemplateToken::Var {
+                name: Name::from_str("foo").unwrap(),
+                default: Some(Box::new(TemplateString::from_text("/default/path"))),
+                required_msg: None,
+                alt: None,
+                span: (0, 21),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_visit_str_variable_with_empty_default() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:-}")
+                .unwrap(),
+            TemplateString(vec![TemplateToken::Var {
+                name: Name::from_str("foo").unwrap(),
+                default: Some(Box::new(TemplateString(vec![]))),
+                required_msg: None,
+                alt: None,
+                span: (0, 8),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_visit_str_variable_with_nested_default() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:-${bar}}")
+                .unwrap(),
+            TemplateString(vec![TemplateToken::Var {
+                name: Name::from_str("foo").unwrap(),
+                default: Some(Box::new(TemplateString(vec![TemplateToken::var(
+                    Name::from_str("bar").unwrap(),
+                    (7, 13)
+                )]))),
+                required_msg: None,
+                alt: None,
+                span: (0, 14),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_visit_str_variable_required() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:?foo must be set}")
+                .unwrap(),
+            TemplateString(vec![TemplateToken::Var {
+                name: Name::from_str("foo").unwrap(),
+                default: None,
+                required_msg: Some("foo must be set".to_string()),
+                alt: None,
+                span: (0, 23),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_visit_str_unclosed_default() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:-unclosed")
+                .unwrap_err()
+                .0,
+            "unclosed variable reference starting at position 0"
+        );
+    }
+
+    #[test]
+    fn test_visit_str_unclosed_required() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:?unclosed")
+                .unwrap_err()
+                .0,
+            "unclosed variable reference starting at position 0"
+        );
+    }
+
+    #[test]
+    fn test_visit_str_invalid_name_with_default() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${invalid-name:-fallback}")
+                .unwrap_err()
+                .0,
+            "invalid variable name at position 0: invalid character `-` at position 7 (must be ASCII alphanumeric or '_')"
+        );
+    }
+
+    #[test]
+    fn test_display_variable_with_default() {
+        let template = TemplateString(vec![TemplateToken::Var {
+            name: Name::from_str("foo").unwrap(),
+            default: Some(Box::new(TemplateString::from_text("bar"))),
+            required_msg: None,
+            alt: None,
+            span: (0, 0),
+        }]);
+        assert_eq!(template.to_string(), "${foo:-bar}");
+    }
+
+    #[test]
+    fn test_display_env_var() {
+        let template = TemplateString(vec![TemplateToken::Env {
+            name: Name::from_str("HOME").unwrap(),
+            default: None,
+            span: (0, 0),
+        }]);
+        assert_eq!(template.to_string(), "${env:HOME}");
+    }
+
+    #[test]
+    fn test_display_env_var_with_default() {
+        let template = TemplateString(vec![TemplateToken::Env {
+            name: Name::from_str("HOME").unwrap(),
+            default: Some(Box::new(TemplateString::from_text("/default/home"))),
+            span: (0, 0),
+        }]);
+        assert_eq!(template.to_string(), "${env:HOME:-/default/home}");
+    }
+
+    #[test]
+    fn test_display_variable_required() {
+        let template = TemplateString(vec![TemplateToken::Var {
+            name: Name::from_str("foo").unwrap(),
+            default: None,
+            required_msg: Some("foo must be set".to_string()),
+            alt: None,
+            span: (0, 0),
+        }]);
+        assert_eq!(template.to_string(), "${foo:?foo must be set}");
+    }
+
+    #[test]
+    fn test_display_variable_with_alt() {
+        let template = TemplateString(vec![TemplateToken::Var {
+            name: Name::from_str("foo").unwrap(),
+            default: None,
+            required_msg: None,
+            alt: Some(Box::new(TemplateString::from_text("bar"))),
+            span: (0, 0),
+        }]);
+        assert_eq!(template.to_string(), "${foo:+bar}");
+    }
+
+    #[test]
+    fn test_visit_str_variable_with_alt() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:+alt-value}")
+                .unwrap(),
+            TemplateString(vec![TemplateToken::Var {
+                name: Name::from_str("foo").unwrap(),
+                default: None,
+                required_msg: None,
+                alt: Some(Box::new(TemplateString::from_text("alt-value"))),
+                span: (0, 17),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_visit_str_variable_with_nested_alt() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:+${bar}}")
+                .unwrap(),
+            TemplateString(vec![TemplateToken::Var {
+                name: Name::from_str("foo").unwrap(),
+                default: None,
+                required_msg: None,
+                alt: Some(Box::new(TemplateString(vec![TemplateToken::var(
+                    Name::from_str("bar").unwrap(),
+                    (7, 13)
+                )]))),
+                span: (0, 14),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_visit_str_unclosed_alt() {
+        assert_eq!(
+            TemplateStringVisitor
+                .visit_str::<TestError>("${foo:+unclosed")
+                .unwrap_err()
+                .0,
+            "unclosed variable reference starting at position 0"
+        );
+    }
 }
 
 impl<'de> Deserialize<'de> for TemplateString {
@@ -301,7 +1212,7 @@ impl<'de> Deserialize<'de> for TemplateString {
     }
 }
 
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields, rename_all = "snake_case")]
 pub(crate) enum JsonFsAccessItem {
     #[serde(rename = "abi.all")]
@@ -415,9 +1326,12 @@ where
             Self::Value(a) => Ok(a),
             Self::Group(g) => {
                 // Simulate a missing variable
-                Ok(g.resolve_bitflags(
-                    abi.ok_or(ResolveError::VariableNotFound(Name::from_str("abi")?))?,
-                ))
+                Ok(
+                    g.resolve_bitflags(abi.ok_or(ResolveError::VariableNotFound {
+                        name: Name::from_str("abi")?,
+                        span: None,
+                    })?),
+                )
             }
         }
     }
@@ -561,7 +1475,111 @@ impl NonEmptySet<JsonFsAccessItem> {
     }
 }
 
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+/// Either a plain [`JsonFsAccessItem`], or an `{ include, exclude }` object
+/// resolving to the included rights minus the excluded ones, e.g. "every
+/// read-write right except socket/fifo creation" without enumerating a dozen
+/// individual items that must be kept in sync as new ABI versions land.
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum JsonFsAccessEntry {
+    Item(JsonFsAccessItem),
+    IncludeExclude(JsonFsAccessIncludeExclude),
+}
+
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct JsonFsAccessIncludeExclude {
+    pub(crate) include: NonEmptySet<JsonFsAccessItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) exclude: Option<NonEmptySet<JsonFsAccessItem>>,
+}
+
+impl JsonFsAccessEntry {
+    fn resolve_bitflags(&self, abi: Option<ABI>) -> Result<BitFlags<AccessFs>, ResolveError> {
+        match self {
+            Self::Item(item) => ValueAccessFs::resolve_bitflags(item, abi),
+            Self::IncludeExclude(entry) => {
+                let included = entry.include.resolve_bitflags(abi)?;
+                let excluded = entry
+                    .exclude
+                    .as_ref()
+                    .map(|exclude| exclude.resolve_bitflags(abi))
+                    .transpose()?
+                    .unwrap_or_default();
+                Ok(included & !excluded)
+            }
+        }
+    }
+}
+
+impl NonEmptySet<JsonFsAccessEntry> {
+    pub fn resolve_bitflags(&self, abi: Option<ABI>) -> Result<BitFlags<AccessFs>, ResolveError> {
+        self.iter().try_fold(BitFlags::EMPTY, |flags, entry| {
+            Ok(flags | entry.resolve_bitflags(abi)?)
+        })
+    }
+}
+
+/// Returns the lowest ABI version under which `right` is available, for
+/// reporting which rights a best-effort ABI downgrade had to drop.
+pub(crate) fn minimum_abi_fs(right: AccessFs) -> ABI {
+    [ABI::V1, ABI::V2, ABI::V3, ABI::V4, ABI::V5, ABI::V6]
+        .into_iter()
+        .find(|&abi| AccessFs::from_all(abi).contains(right))
+        .unwrap_or(ABI::V6)
+}
+
+/// Decomposes a set of access rights into the concrete (non-group) item
+/// names that the schema accepts, for serialization purposes.
+///
+/// This always expands to individual rights rather than folding them back
+/// into `abi.*`/`vN.*` aliases, so the output is unambiguous regardless of
+/// which ABI produced it.
+pub(crate) fn access_fs_items(access: BitFlags<AccessFs>) -> BTreeSet<JsonFsAccessItem> {
+    [
+        (AccessFs::Execute, JsonFsAccessItem::Execute),
+        (AccessFs::WriteFile, JsonFsAccessItem::WriteFile),
+        (AccessFs::ReadFile, JsonFsAccessItem::ReadFile),
+        (AccessFs::ReadDir, JsonFsAccessItem::ReadDir),
+        (AccessFs::RemoveDir, JsonFsAccessItem::RemoveDir),
+        (AccessFs::RemoveFile, JsonFsAccessItem::RemoveFile),
+        (AccessFs::MakeChar, JsonFsAccessItem::MakeChar),
+        (AccessFs::MakeDir, JsonFsAccessItem::MakeDir),
+        (AccessFs::MakeReg, JsonFsAccessItem::MakeReg),
+        (AccessFs::MakeSock, JsonFsAccessItem::MakeSock),
+        (AccessFs::MakeFifo, JsonFsAccessItem::MakeFifo),
+        (AccessFs::MakeBlock, JsonFsAccessItem::MakeBlock),
+        (AccessFs::MakeSym, JsonFsAccessItem::MakeSym),
+        (AccessFs::Refer, JsonFsAccessItem::Refer),
+        (AccessFs::Truncate, JsonFsAccessItem::Truncate),
+        (AccessFs::IoctlDev, JsonFsAccessItem::IoctlDev),
+    ]
+    .into_iter()
+    .filter_map(|(right, item)| access.contains(right).then_some(item))
+    .collect()
+}
+
+/// Like [`access_fs_items`], but first tries to fold `access` back into the
+/// `abi.all`/`abi.read_execute`/`abi.read_write` alias it exactly matches
+/// under `abi`, falling back to the concrete decomposition when no alias
+/// fits.
+pub(crate) fn fold_access_fs_items(
+    access: BitFlags<AccessFs>,
+    abi: ABI,
+) -> BTreeSet<JsonFsAccessItem> {
+    for (group, item) in [
+        (AbiGroupFs::All, JsonFsAccessItem::AbiAll),
+        (AbiGroupFs::ReadExecute, JsonFsAccessItem::AbiReadExecute),
+        (AbiGroupFs::ReadWrite, JsonFsAccessItem::AbiReadWrite),
+    ] {
+        if group.resolve_bitflags(abi) == access {
+            return [item].into();
+        }
+    }
+    access_fs_items(access)
+}
+
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields, rename_all = "snake_case")]
 pub(crate) enum JsonNetAccessItem {
     #[serde(rename = "abi.all")]
@@ -612,7 +1630,83 @@ impl NonEmptySet<JsonNetAccessItem> {
     }
 }
 
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+/// See [`JsonFsAccessEntry`].
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum JsonNetAccessEntry {
+    Item(JsonNetAccessItem),
+    IncludeExclude(JsonNetAccessIncludeExclude),
+}
+
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct JsonNetAccessIncludeExclude {
+    pub(crate) include: NonEmptySet<JsonNetAccessItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) exclude: Option<NonEmptySet<JsonNetAccessItem>>,
+}
+
+impl JsonNetAccessEntry {
+    fn resolve_bitflags(&self, abi: Option<ABI>) -> Result<BitFlags<AccessNet>, ResolveError> {
+        match self {
+            Self::Item(item) => ValueAccessNet::resolve_bitflags(item, abi),
+            Self::IncludeExclude(entry) => {
+                let included = entry.include.resolve_bitflags(abi)?;
+                let excluded = entry
+                    .exclude
+                    .as_ref()
+                    .map(|exclude| exclude.resolve_bitflags(abi))
+                    .transpose()?
+                    .unwrap_or_default();
+                Ok(included & !excluded)
+            }
+        }
+    }
+}
+
+impl NonEmptySet<JsonNetAccessEntry> {
+    pub fn resolve_bitflags(&self, abi: Option<ABI>) -> Result<BitFlags<AccessNet>, ResolveError> {
+        self.iter().try_fold(BitFlags::EMPTY, |flags, entry| {
+            Ok(flags | entry.resolve_bitflags(abi)?)
+        })
+    }
+}
+
+/// Returns the lowest ABI version under which `right` is available. See
+/// [`minimum_abi_fs`].
+pub(crate) fn minimum_abi_net(right: AccessNet) -> ABI {
+    [ABI::V1, ABI::V2, ABI::V3, ABI::V4, ABI::V5, ABI::V6]
+        .into_iter()
+        .find(|&abi| AccessNet::from_all(abi).contains(right))
+        .unwrap_or(ABI::V6)
+}
+
+/// Decomposes a set of network access rights into the concrete item names
+/// the schema accepts. See [`access_fs_items`].
+pub(crate) fn access_net_items(access: BitFlags<AccessNet>) -> BTreeSet<JsonNetAccessItem> {
+    [
+        (AccessNet::BindTcp, JsonNetAccessItem::BindTcp),
+        (AccessNet::ConnectTcp, JsonNetAccessItem::ConnectTcp),
+    ]
+    .into_iter()
+    .filter_map(|(right, item)| access.contains(right).then_some(item))
+    .collect()
+}
+
+/// Like [`access_net_items`], but folds `access` into `abi.all` when it
+/// matches exactly under `abi`. See [`fold_access_fs_items`].
+pub(crate) fn fold_access_net_items(
+    access: BitFlags<AccessNet>,
+    abi: ABI,
+) -> BTreeSet<JsonNetAccessItem> {
+    if AbiGroupNet::All.resolve_bitflags(abi) == access {
+        [JsonNetAccessItem::AbiAll].into()
+    } else {
+        access_net_items(access)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields, rename_all = "snake_case")]
 pub(crate) enum JsonScopeItem {
     #[serde(rename = "abi.all")]
@@ -657,14 +1751,90 @@ impl NonEmptySet<JsonScopeItem> {
     }
 }
 
+/// See [`JsonFsAccessEntry`].
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum JsonScopeEntry {
+    Item(JsonScopeItem),
+    IncludeExclude(JsonScopeIncludeExclude),
+}
+
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct JsonScopeIncludeExclude {
+    pub(crate) include: NonEmptySet<JsonScopeItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) exclude: Option<NonEmptySet<JsonScopeItem>>,
+}
+
+impl JsonScopeEntry {
+    fn resolve_bitflags(&self, abi: Option<ABI>) -> Result<BitFlags<Scope>, ResolveError> {
+        match self {
+            Self::Item(item) => ValueScope::resolve_bitflags(item, abi),
+            Self::IncludeExclude(entry) => {
+                let included = entry.include.resolve_bitflags(abi)?;
+                let excluded = entry
+                    .exclude
+                    .as_ref()
+                    .map(|exclude| exclude.resolve_bitflags(abi))
+                    .transpose()?
+                    .unwrap_or_default();
+                Ok(included & !excluded)
+            }
+        }
+    }
+}
+
+impl NonEmptySet<JsonScopeEntry> {
+    pub fn resolve_bitflags(&self, abi: Option<ABI>) -> Result<BitFlags<Scope>, ResolveError> {
+        self.iter().try_fold(BitFlags::EMPTY, |flags, entry| {
+            Ok(flags | entry.resolve_bitflags(abi)?)
+        })
+    }
+}
+
+/// Returns the lowest ABI version under which `right` is available. See
+/// [`minimum_abi_fs`].
+pub(crate) fn minimum_abi_scope(right: Scope) -> ABI {
+    [ABI::V1, ABI::V2, ABI::V3, ABI::V4, ABI::V5, ABI::V6]
+        .into_iter()
+        .find(|&abi| Scope::from_all(abi).contains(right))
+        .unwrap_or(ABI::V6)
+}
+
+/// Decomposes a set of scopes into the concrete item names the schema
+/// accepts. See [`access_fs_items`].
+pub(crate) fn scope_items(access: BitFlags<Scope>) -> BTreeSet<JsonScopeItem> {
+    [
+        (Scope::AbstractUnixSocket, JsonScopeItem::AbstractUnixSocket),
+        (Scope::Signal, JsonScopeItem::Signal),
+    ]
+    .into_iter()
+    .filter_map(|(right, item)| access.contains(right).then_some(item))
+    .collect()
+}
+
+/// Like [`scope_items`], but folds `access` into `abi.all` when it matches
+/// exactly under `abi`. See [`fold_access_fs_items`].
+pub(crate) fn fold_scope_items(access: BitFlags<Scope>, abi: ABI) -> BTreeSet<JsonScopeItem> {
+    if AbiGroupScope::All.resolve_bitflags(abi) == access {
+        [JsonScopeItem::AbiAll].into()
+    } else {
+        scope_items(access)
+    }
+}
+
 // At least one of the fields must be set, which is guaranteed when wrapped with NonEmptyStruct.
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
 pub(crate) struct JsonRuleset {
-    pub(crate) handledAccessFs: Option<NonEmptySet<JsonFsAccessItem>>,
-    pub(crate) handledAccessNet: Option<NonEmptySet<JsonNetAccessItem>>,
-    pub(crate) scoped: Option<NonEmptySet<JsonScopeItem>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) handledAccessFs: Option<NonEmptySet<JsonFsAccessEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) handledAccessNet: Option<NonEmptySet<JsonNetAccessEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) scoped: Option<NonEmptySet<JsonScopeEntry>>,
 }
 
 impl NonEmptyStructInner for JsonRuleset {
@@ -683,12 +1853,15 @@ impl NonEmptyStructInner for JsonRuleset {
 }
 
 // At least one of the fields must be set, which is guaranteed when wrapped with NonEmptyStruct.
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields)]
 struct TomlRuleset {
-    handled_access_fs: Option<NonEmptySet<JsonFsAccessItem>>,
-    handled_access_net: Option<NonEmptySet<JsonNetAccessItem>>,
-    scoped: Option<NonEmptySet<JsonScopeItem>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    handled_access_fs: Option<NonEmptySet<JsonFsAccessEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    handled_access_net: Option<NonEmptySet<JsonNetAccessEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scoped: Option<NonEmptySet<JsonScopeEntry>>,
 }
 
 impl NonEmptyStructInner for TomlRuleset {
@@ -716,20 +1889,30 @@ impl From<TomlRuleset> for JsonRuleset {
     }
 }
 
+impl From<JsonRuleset> for TomlRuleset {
+    fn from(json: JsonRuleset) -> Self {
+        Self {
+            handled_access_fs: json.handledAccessFs,
+            handled_access_net: json.handledAccessNet,
+            scoped: json.scoped,
+        }
+    }
+}
+
 // TODO: Make paths canonical (e.g. remove extra slashes and dots) and only open the same paths
 // once.
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
 pub(crate) struct JsonPathBeneath {
-    pub(crate) allowedAccess: NonEmptySet<JsonFsAccessItem>,
+    pub(crate) allowedAccess: NonEmptySet<JsonFsAccessEntry>,
     pub(crate) parent: NonEmptySet<TemplateString>,
 }
 
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields)]
 struct TomlPathBeneath {
-    allowed_access: NonEmptySet<JsonFsAccessItem>,
+    allowed_access: NonEmptySet<JsonFsAccessEntry>,
     parent: NonEmptySet<TemplateString>,
 }
 
@@ -742,19 +1925,168 @@ impl From<TomlPathBeneath> for JsonPathBeneath {
     }
 }
 
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+impl From<JsonPathBeneath> for TomlPathBeneath {
+    fn from(json: JsonPathBeneath) -> Self {
+        Self {
+            allowed_access: json.allowedAccess,
+            parent: json.parent,
+        }
+    }
+}
+
+/// An inclusive `0..=65535` port range, accepted in the schema either as a
+/// bare integer (a single port) or as a `"lo-hi"` string.
+#[derive(Debug, Clone, Copy, Ord, Eq, PartialOrd, PartialEq)]
+pub(crate) struct PortRange {
+    pub(crate) start: u64,
+    pub(crate) end: u64,
+}
+
+impl PortRange {
+    const MAX_PORT: u64 = u16::MAX as u64;
+
+    pub(crate) fn single(port: u64) -> Self {
+        Self {
+            start: port,
+            end: port,
+        }
+    }
+
+    /// Iterates over every port covered by this range.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = u64> {
+        self.start..=self.end
+    }
+}
+
+struct PortRangeVisitor;
+
+impl PortRangeVisitor {
+    fn from_bounds<E>(start: u64, end: u64) -> Result<PortRange, E>
+    where
+        E: de::Error,
+    {
+        if start > end {
+            return Err(E::custom(format!(
+                "invalid port range (start greater than end): {start}-{end}"
+            )));
+        }
+        if end > PortRange::MAX_PORT {
+            return Err(E::custom(format!(
+                "port must be between 0 and {} (inclusive)",
+                PortRange::MAX_PORT
+            )));
+        }
+        Ok(PortRange { start, end })
+    }
+}
+
+impl<'de> Visitor<'de> for PortRangeVisitor {
+    type Value = PortRange;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a port number or a \"lo-hi\" port range")
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<PortRange, E>
+    where
+        E: de::Error,
+    {
+        Self::from_bounds(value, value)
+    }
+
+    // Needed for TOML integers.
+    fn visit_i64<E>(self, value: i64) -> Result<PortRange, E>
+    where
+        E: de::Error,
+    {
+        let value: u64 = value
+            .try_into()
+            .map_err(|_| E::invalid_value(Unexpected::Signed(value), &"a non-negative port"))?;
+        Self::from_bounds(value, value)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<PortRange, E>
+    where
+        E: de::Error,
+    {
+        let (start, end) = value.split_once('-').ok_or_else(|| {
+            E::custom(format!("invalid port range (expected \"lo-hi\"): {value}"))
+        })?;
+        let start: u64 = start
+            .trim()
+            .parse()
+            .map_err(|_| E::custom(format!("invalid port range: {value}")))?;
+        let end: u64 = end
+            .trim()
+            .parse()
+            .map_err(|_| E::custom(format!("invalid port range: {value}")))?;
+        Self::from_bounds(start, end)
+    }
+}
+
+impl<'de> Deserialize<'de> for PortRange {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(PortRangeVisitor)
+    }
+}
+
+impl Serialize for PortRange {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.start == self.end {
+            serializer.serialize_u64(self.start)
+        } else {
+            serializer.serialize_str(&format!("{}-{}", self.start, self.end))
+        }
+    }
+}
+
+#[test]
+fn test_port_range_single() {
+    let range: PortRange = serde_json::from_str("443").unwrap();
+    assert_eq!(range.start, 443);
+    assert_eq!(range.end, 443);
+    assert_eq!(serde_json::to_string(&range).unwrap(), "443");
+}
+
+#[test]
+fn test_port_range_from_string() {
+    let range: PortRange = serde_json::from_str(r#""1024-65535""#).unwrap();
+    assert_eq!(range.start, 1024);
+    assert_eq!(range.end, 65535);
+    assert_eq!(serde_json::to_string(&range).unwrap(), r#""1024-65535""#);
+}
+
+#[test]
+fn test_port_range_reversed() {
+    let result: Result<PortRange, _> = serde_json::from_str(r#""65535-1024""#);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_port_range_out_of_bounds() {
+    let result: Result<PortRange, _> = serde_json::from_str("65536");
+    assert!(result.is_err());
+}
+
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
 pub(crate) struct JsonNetPort {
-    pub(crate) allowedAccess: NonEmptySet<JsonNetAccessItem>,
-    pub(crate) port: NonEmptySet<u64>,
+    pub(crate) allowedAccess: NonEmptySet<JsonNetAccessEntry>,
+    pub(crate) port: NonEmptySet<PortRange>,
 }
 
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields)]
 struct TomlNetPort {
-    allowed_access: NonEmptySet<JsonNetAccessItem>,
-    port: NonEmptySet<u64>,
+    allowed_access: NonEmptySet<JsonNetAccessEntry>,
+    port: NonEmptySet<PortRange>,
 }
 
 impl From<TomlNetPort> for JsonNetPort {
@@ -766,6 +2098,81 @@ impl From<TomlNetPort> for JsonNetPort {
     }
 }
 
+impl From<JsonNetPort> for TomlNetPort {
+    fn from(json: JsonNetPort) -> Self {
+        Self {
+            allowed_access: json.allowedAccess,
+            port: json.port,
+        }
+    }
+}
+
+/// A named overlay applied on top of a [`JsonConfig`]'s base rules by
+/// `Config::parse_json_with_profile`/`Config::parse_toml_with_profile`,
+/// before [`NonEmptySet::resolve_bitflags`] turns any of its access sets into
+/// concrete bitflags. Every field here is unioned into the matching
+/// [`JsonConfig`] field (same semantics as [`Config::merge`]), so declaring
+/// the same path or port in both the base document and a profile simply OR's
+/// their access rights together.
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub(crate) struct JsonProfile {
+    pub(crate) name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) ruleset: Option<NonEmptySet<NonEmptyStruct<JsonRuleset>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pathBeneath: Option<NonEmptySet<JsonPathBeneath>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) netPort: Option<NonEmptySet<JsonNetPort>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct TomlProfile {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ruleset: Option<NonEmptySet<NonEmptyStruct<TomlRuleset>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path_beneath: Option<NonEmptySet<TomlPathBeneath>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    net_port: Option<NonEmptySet<TomlNetPort>>,
+}
+
+impl From<TomlProfile> for JsonProfile {
+    fn from(toml: TomlProfile) -> Self {
+        Self {
+            name: toml.name,
+            ruleset: toml
+                .ruleset
+                .map(|set| set.into_iter().map(|r| r.convert()).collect()),
+            pathBeneath: toml
+                .path_beneath
+                .map(|set| set.into_iter().map(Into::into).collect()),
+            netPort: toml
+                .net_port
+                .map(|set| set.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl From<JsonProfile> for TomlProfile {
+    fn from(json: JsonProfile) -> Self {
+        Self {
+            name: json.name,
+            ruleset: json
+                .ruleset
+                .map(|set| set.into_iter().map(|r| r.convert()).collect()),
+            path_beneath: json
+                .pathBeneath
+                .map(|set| set.into_iter().map(Into::into).collect()),
+            net_port: json
+                .netPort
+                .map(|set| set.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
 struct JsonAbiVisitor;
 
 impl JsonAbiVisitor {
@@ -808,7 +2215,7 @@ impl<'de> Visitor<'de> for JsonAbiVisitor {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct JsonAbi(i32);
 
 impl From<JsonAbi> for ABI {
@@ -826,55 +2233,357 @@ impl<'de> serde::Deserialize<'de> for JsonAbi {
     }
 }
 
-#[derive(Debug, Deserialize, Ord, Eq, PartialOrd, PartialEq)]
+impl Serialize for JsonAbi {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i32(self.0)
+    }
+}
+
+/// The `abi` field's value: a single version, `"latest"` (the highest ABI
+/// this crate knows about, resolved the same way [`ABI::from`] clamps an
+/// out-of-range integer, see `test_i32`), or an explicit `{ min, max }`
+/// range. A range clamps the config's effective handled-access sets to the
+/// intersection of what every ABI in `min..=max` supports, i.e. `min`'s own
+/// access set, since later ABIs are strict supersets of earlier ones; see
+/// [`crate::config::AbiRequirement`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JsonAbiRange {
+    Exact(JsonAbi),
+    Latest,
+    Range { min: JsonAbi, max: JsonAbi },
+}
+
+impl JsonAbiRange {
+    /// The single version this requirement compares as "newer" against
+    /// another: the version itself for `Exact`, `i32::MAX` (the same sentinel
+    /// `test_i32` uses to mean "whatever this crate's highest ABI is") for
+    /// `Latest`, or the top of the range for `Range`. Used by
+    /// [`JsonConfig::union`]'s "higher wins" merge rule.
+    fn effective(&self) -> JsonAbi {
+        match *self {
+            JsonAbiRange::Exact(abi) => abi,
+            JsonAbiRange::Latest => JsonAbi(i32::MAX),
+            JsonAbiRange::Range { max, .. } => max,
+        }
+    }
+}
+
+struct JsonAbiRangeVisitor;
+
+impl<'de> Visitor<'de> for JsonAbiRangeVisitor {
+    type Value = JsonAbiRange;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(r#"a Landlock ABI version, "latest", or a { min, max } range"#)
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<JsonAbiRange, E>
+    where
+        E: de::Error,
+    {
+        JsonAbiVisitor::visit_integer(value, Unexpected::Unsigned(value)).map(JsonAbiRange::Exact)
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<JsonAbiRange, E>
+    where
+        E: de::Error,
+    {
+        JsonAbiVisitor::visit_integer(value, Unexpected::Signed(value)).map(JsonAbiRange::Exact)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<JsonAbiRange, E>
+    where
+        E: de::Error,
+    {
+        if value == "latest" {
+            Ok(JsonAbiRange::Latest)
+        } else {
+            Err(E::invalid_value(Unexpected::Str(value), &"\"latest\""))
+        }
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<JsonAbiRange, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "snake_case")]
+        enum Field {
+            Min,
+            Max,
+        }
+
+        let mut min = None;
+        let mut max = None;
+        while let Some(key) = map.next_key::<Field>()? {
+            match key {
+                Field::Min => {
+                    if min.is_some() {
+                        return Err(de::Error::duplicate_field("min"));
+                    }
+                    min = Some(map.next_value::<JsonAbi>()?);
+                }
+                Field::Max => {
+                    if max.is_some() {
+                        return Err(de::Error::duplicate_field("max"));
+                    }
+                    max = Some(map.next_value::<JsonAbi>()?);
+                }
+            }
+        }
+        let min = min.ok_or_else(|| de::Error::missing_field("min"))?;
+        let max = max.ok_or_else(|| de::Error::missing_field("max"))?;
+        if min > max {
+            return Err(de::Error::custom(format!(
+                "ABI range min ({}) must not be greater than max ({})",
+                min.0, max.0
+            )));
+        }
+        Ok(JsonAbiRange::Range { min, max })
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonAbiRange {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(JsonAbiRangeVisitor)
+    }
+}
+
+impl Serialize for JsonAbiRange {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            JsonAbiRange::Exact(abi) => abi.serialize(serializer),
+            JsonAbiRange::Latest => serializer.serialize_str("latest"),
+            JsonAbiRange::Range { min, max } => {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("min", min)?;
+                map.serialize_entry("max", max)?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// Serialized form of [`crate::config::CompatLevel`], selecting how strictly
+/// a `Config` should be negotiated against the running kernel's Landlock ABI
+/// when it's later turned into a ruleset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JsonCompatLevel {
+    BestEffort,
+    SoftRequirement,
+    HardRequirement,
+}
+
+#[derive(Debug, Deserialize, Serialize, Ord, Eq, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
 pub(crate) struct JsonVariable {
     pub(crate) name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) literal: Option<NonEmptySet<String>>,
 }
 
 type TomlVariable = JsonVariable;
 
 // At least one of the fields must be set, which is guaranteed when wrapped with NonEmptyStruct.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
 pub(crate) struct JsonConfig {
-    pub(crate) abi: Option<JsonAbi>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) abi: Option<JsonAbiRange>,
+    // How strictly to negotiate against the running kernel's Landlock ABI
+    // once this document becomes a `Config`; see `JsonCompatLevel`. Absent
+    // means `CompatLevel::default()`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) compatibility: Option<JsonCompatLevel>,
+    // Paths to other config files to load and merge (base policy first,
+    // deepest `include` wins) before this document's own rules are applied.
+    // Resolved by `Config::parse_json_file`; `Config::parse_json` itself
+    // does not act on this field since it has no filesystem context.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) include: Option<NonEmptySet<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) variable: Option<NonEmptySet<JsonVariable>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) ruleset: Option<NonEmptySet<NonEmptyStruct<JsonRuleset>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) pathBeneath: Option<NonEmptySet<JsonPathBeneath>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) netPort: Option<NonEmptySet<JsonNetPort>>,
+    // Named overlays selectable through
+    // `Config::parse_json_with_profile`/`Config::parse_toml_with_profile`,
+    // see `JsonProfile`. Deliberately excluded from `is_empty` below: a
+    // profile alone cannot satisfy the non-empty-configuration invariant, it
+    // can only add to a base document that already has some rules of its
+    // own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) profiles: Option<NonEmptySet<JsonProfile>>,
 }
 
 impl NonEmptyStructInner for JsonConfig {
     const ERROR_MESSAGE: &'static str = "empty configuration";
 
     fn is_empty(&self) -> bool {
-        self.variable.as_ref().is_none_or(|set| set.is_empty())
+        self.include.as_ref().is_none_or(|set| set.is_empty())
+            && self.variable.as_ref().is_none_or(|set| set.is_empty())
             && self.ruleset.as_ref().is_none_or(|set| set.is_empty())
             && self.pathBeneath.as_ref().is_none_or(|set| set.is_empty())
             && self.netPort.as_ref().is_none_or(|set| set.is_empty())
     }
 }
 
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ProfileError {
+    #[error("unknown profile: `{0}`")]
+    UnknownProfile(String),
+}
+
+impl JsonConfig {
+    /// Selects `profile` from this document's `profiles` list and unions its
+    /// rules onto the base `ruleset`/`pathBeneath`/`netPort` sets, consuming
+    /// `profiles` in the process. Must run before this [`JsonConfig`] is
+    /// converted into a [`crate::Config`], since that conversion is where
+    /// [`NonEmptySet::resolve_bitflags`] turns the merged access sets into
+    /// concrete bitflags.
+    pub(crate) fn select_profile(mut self, profile: &str) -> Result<Self, ProfileError> {
+        let profiles = self.profiles.take().unwrap_or_default();
+        let selected = profiles
+            .into_iter()
+            .find(|p| p.name == profile)
+            .ok_or_else(|| ProfileError::UnknownProfile(profile.to_string()))?;
+
+        if let Some(ruleset) = selected.ruleset {
+            self.ruleset = Some(match self.ruleset.take() {
+                Some(base) => base.into_iter().chain(ruleset).collect(),
+                None => ruleset,
+            });
+        }
+        if let Some(path_beneath) = selected.pathBeneath {
+            self.pathBeneath = Some(match self.pathBeneath.take() {
+                Some(base) => base.into_iter().chain(path_beneath).collect(),
+                None => path_beneath,
+            });
+        }
+        if let Some(net_port) = selected.netPort {
+            self.netPort = Some(match self.netPort.take() {
+                Some(base) => base.into_iter().chain(net_port).collect(),
+                None => net_port,
+            });
+        }
+
+        Ok(self)
+    }
+
+    /// Fills in any `variable` entry whose `literal` is absent from the
+    /// environment variable of the same name, splitting its value on `:`
+    /// the way `PATH`-like lists do. Opt-in: plain [`Config::parse_json`]/
+    /// [`Config::parse_toml`] leave a missing `literal` as an empty set, so
+    /// only callers that want machine-specific values pulled from the
+    /// environment need to call [`Config::parse_json_with_env`] or
+    /// [`Config::parse_toml_with_env`], which run this after
+    /// [`From<TomlConfig> for JsonConfig`](TomlConfig) so both formats
+    /// benefit equally. Fails loudly with [`ResolveError::EnvVarNotFound`]
+    /// rather than silently falling back to an empty set.
+    pub(crate) fn resolve_env_variables(mut self) -> Result<Self, ResolveError> {
+        let Some(variables) = self.variable.take() else {
+            return Ok(self);
+        };
+
+        let resolved = variables
+            .into_iter()
+            .map(|variable| match variable.literal {
+                Some(_) => Ok(variable),
+                None => {
+                    let value = std::env::var(&variable.name)
+                        .map_err(|_| ResolveError::EnvVarNotFound(variable.name.clone()))?;
+                    Ok(JsonVariable {
+                        name: variable.name,
+                        literal: Some(value.split(':').map(str::to_string).collect()),
+                    })
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        self.variable = Some(resolved);
+        Ok(self)
+    }
+
+    /// Unions `other` onto this document for hierarchical layering (e.g.
+    /// [`Config::discover_merged`]): each `NonEmptySet` field is extended
+    /// with `other`'s items (`BTreeSet` semantics dedupe exact duplicates),
+    /// `abi` becomes the higher of the two, `other` winning ties, and
+    /// `compatibility` takes `other`'s value when set, else `self`'s.
+    /// Unlike merging already-resolved [`crate::Config`]s, this keeps
+    /// `abi.*`/`vN.*` access aliases symbolic across layers instead of
+    /// expanding them to bitflags before combining.
+    pub(crate) fn union(mut self, other: Self) -> Self {
+        self.abi = match (self.abi, other.abi) {
+            (Some(a), Some(b)) => Some(if a.effective() > b.effective() { a } else { b }),
+            (a, None) => a,
+            (None, b) => b,
+        };
+        self.compatibility = other.compatibility.or(self.compatibility);
+        self.include = union_sets(self.include.take(), other.include);
+        self.variable = union_sets(self.variable.take(), other.variable);
+        self.ruleset = union_sets(self.ruleset.take(), other.ruleset);
+        self.pathBeneath = union_sets(self.pathBeneath.take(), other.pathBeneath);
+        self.netPort = union_sets(self.netPort.take(), other.netPort);
+        self.profiles = union_sets(self.profiles.take(), other.profiles);
+        self
+    }
+}
+
+fn union_sets<T: Ord>(
+    a: Option<NonEmptySet<T>>,
+    b: Option<NonEmptySet<T>>,
+) -> Option<NonEmptySet<T>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.into_iter().chain(b).collect()),
+        (Some(a), None) => Some(a),
+        (None, b) => b,
+    }
+}
+
 // At least one of the fields must be set, which is guaranteed when wrapped with NonEmptyStruct.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct TomlConfig {
-    abi: Option<JsonAbi>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    abi: Option<JsonAbiRange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compatibility: Option<JsonCompatLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    include: Option<NonEmptySet<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     variable: Option<NonEmptySet<TomlVariable>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     ruleset: Option<NonEmptySet<NonEmptyStruct<TomlRuleset>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     path_beneath: Option<NonEmptySet<TomlPathBeneath>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     net_port: Option<NonEmptySet<TomlNetPort>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profiles: Option<NonEmptySet<TomlProfile>>,
 }
 
 impl NonEmptyStructInner for TomlConfig {
     const ERROR_MESSAGE: &'static str = "empty configuration";
 
     fn is_empty(&self) -> bool {
-        self.variable.as_ref().is_none_or(|set| set.is_empty())
+        self.include.as_ref().is_none_or(|set| set.is_empty())
+            && self.variable.as_ref().is_none_or(|set| set.is_empty())
             && self.ruleset.as_ref().is_none_or(|set| set.is_empty())
             && self.path_beneath.as_ref().is_none_or(|set| set.is_empty())
             && self.net_port.as_ref().is_none_or(|set| set.is_empty())
@@ -885,6 +2594,8 @@ impl From<TomlConfig> for JsonConfig {
     fn from(toml: TomlConfig) -> Self {
         Self {
             abi: toml.abi,
+            compatibility: toml.compatibility,
+            include: toml.include,
             variable: toml.variable,
             ruleset: toml
                 .ruleset
@@ -895,6 +2606,34 @@ impl From<TomlConfig> for JsonConfig {
             netPort: toml
                 .net_port
                 .map(|set| set.into_iter().map(Into::into).collect()),
+            profiles: toml
+                .profiles
+                .map(|set| set.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+/// Reverse of [`From<TomlConfig> for JsonConfig`], used when serializing a
+/// config back out as TOML.
+impl From<JsonConfig> for TomlConfig {
+    fn from(json: JsonConfig) -> Self {
+        Self {
+            abi: json.abi,
+            compatibility: json.compatibility,
+            include: json.include,
+            variable: json.variable,
+            ruleset: json
+                .ruleset
+                .map(|set| set.into_iter().map(|r| r.convert()).collect()),
+            path_beneath: json
+                .pathBeneath
+                .map(|set| set.into_iter().map(Into::into).collect()),
+            net_port: json
+                .netPort
+                .map(|set| set.into_iter().map(Into::into).collect()),
+            profiles: json
+                .profiles
+                .map(|set| set.into_iter().map(Into::into).collect()),
         }
     }
 }
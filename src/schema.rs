@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! The JSON Schema for landlockconfig's JSON configuration format, embedded
+//! at compile time with `include_str!` and compiled into a
+//! [`jsonschema::Validator`] once on first use.
+//!
+//! `tests_helpers::JSON_VALIDATOR` used to read `schema/landlockconfig.json`
+//! from `CARGO_MANIFEST_DIR` at test time, which only worked inside this
+//! crate's own source checkout. Embedding the schema guarantees downstream
+//! tools validate configs against the exact schema shipped in the compiled
+//! crate, not a copy that can drift or go missing.
+
+use serde_json::Value;
+use std::fmt;
+use std::sync::OnceLock;
+
+const SCHEMA_STR: &str = include_str!("../schema/landlockconfig.json");
+
+fn schema_value() -> &'static Value {
+    static SCHEMA: OnceLock<Value> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        serde_json::from_str(SCHEMA_STR).expect("embedded JSON schema is invalid JSON")
+    })
+}
+
+fn validator() -> &'static jsonschema::Validator {
+    static VALIDATOR: OnceLock<jsonschema::Validator> = OnceLock::new();
+    VALIDATOR.get_or_init(|| {
+        jsonschema::validator_for(schema_value()).expect("embedded JSON schema is invalid")
+    })
+}
+
+/// Returns the JSON Schema landlockconfig validates configs against, as
+/// embedded in this build of the crate.
+pub fn schema() -> &'static Value {
+    schema_value()
+}
+
+/// A single schema violation.
+///
+/// `pointer` is the [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901)
+/// into the config element that failed validation (e.g.
+/// `/ruleset/0/handledAccessFs/2`), `keyword` is the schema keyword it
+/// failed (e.g. `enum`), and `message` is jsonschema's human-readable
+/// description of the failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LandlockConfigError {
+    pub pointer: String,
+    pub keyword: String,
+    pub message: String,
+}
+
+impl fmt::Display for LandlockConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at `{}`", self.message, self.pointer)
+    }
+}
+
+impl std::error::Error for LandlockConfigError {}
+
+/// The last non-empty segment of a JSON Pointer, e.g. `"enum"` for
+/// `/properties/ruleset/items/properties/handledAccessFs/items/enum`.
+fn keyword_from_schema_path(schema_path: &str) -> String {
+    schema_path
+        .rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or(schema_path)
+        .to_owned()
+}
+
+/// Validates `value` against [`schema()`], collecting every violation in one
+/// pass instead of stopping at the first one.
+pub fn validate_config(value: &Value) -> Result<(), Vec<LandlockConfigError>> {
+    let errors: Vec<LandlockConfigError> = validator()
+        .iter_errors(value)
+        .map(|e| LandlockConfigError {
+            pointer: e.instance_path.to_string(),
+            keyword: keyword_from_schema_path(&e.schema_path.to_string()),
+            message: e.to_string(),
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_is_valid_json() {
+        assert!(schema().is_object());
+    }
+
+    #[test]
+    fn test_validate_config_accepts_valid_config() {
+        let value: Value =
+            serde_json::from_str(r#"{ "ruleset": [ { "handledAccessFs": [ "execute" ] } ] }"#)
+                .unwrap();
+        assert_eq!(validate_config(&value), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_config_rejects_unknown_access_right() {
+        let value: Value = serde_json::from_str(
+            r#"{ "ruleset": [ { "handledAccessFs": [ "not_a_real_right" ] } ] }"#,
+        )
+        .unwrap();
+        let errors = validate_config(&value).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].pointer, "/ruleset/0/handledAccessFs/0");
+        assert_eq!(errors[0].keyword, "oneOf");
+    }
+
+    #[test]
+    fn test_validate_config_rejects_empty_document() {
+        let value: Value = serde_json::from_str("{}").unwrap();
+        assert!(validate_config(&value).is_err());
+    }
+}
@@ -3,7 +3,7 @@
 use crate::{
     parser::TemplateString,
     tests_helpers::{parse_json, parse_json_schema, parse_toml, LATEST_ABI},
-    Config,
+    AbiRequirement, Config,
 };
 use landlock::{Access, AccessFs, AccessNet, Scope, ABI};
 use serde_json::error::Category;
@@ -29,7 +29,7 @@ fn test_access_fs_with_value() {
     "#;
 
     let config = Config {
-        abi: Some(ABI::V2),
+        abi: Some(AbiRequirement::Exact(ABI::V2)),
         handled_fs: AccessFs::from_all(ABI::V2),
         ..Default::default()
     };
@@ -91,7 +91,7 @@ fn test_format_ok() {
     "#;
 
     let config = Config {
-        abi: Some(ABI::V1),
+        abi: Some(AbiRequirement::Exact(ABI::V1)),
         handled_fs: AccessFs::Execute.into(),
         ..Default::default()
     };
@@ -173,7 +173,7 @@ fn test_all_versions_abi_all() {
 
         let abi = version.into();
         let mut config = Config {
-            abi: Some(abi),
+            abi: Some(AbiRequirement::Exact(abi)),
             handled_fs: AccessFs::from_all(abi),
             handled_net: AccessNet::from_all(abi),
             scoped: Scope::from_all(abi),
@@ -190,6 +190,79 @@ fn test_all_versions_abi_all() {
     }
 }
 
+#[test]
+fn test_compatibility_defaults_to_best_effort() {
+    let json = r#"{
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ]
+    }"#;
+    let config = Config {
+        handled_fs: AccessFs::Execute.into(),
+        ..Default::default()
+    };
+    assert_eq!(parse_json(json).unwrap(), config);
+    assert_eq!(config.compatibility, crate::CompatLevel::BestEffort);
+}
+
+#[test]
+fn test_compatibility_hard_requirement() {
+    let json = r#"{
+        "compatibility": "hard_requirement",
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ]
+    }"#;
+    let toml = r#"
+        compatibility = "hard_requirement"
+        [[ruleset]]
+        handled_access_fs = [ "execute" ]
+    "#;
+
+    let mut config = Config {
+        handled_fs: AccessFs::Execute.into(),
+        ..Default::default()
+    };
+    config.compatibility = crate::CompatLevel::HardRequirement;
+
+    assert_eq!(parse_json(json).unwrap(), config);
+    assert_eq!(parse_toml(toml).unwrap(), config);
+}
+
+#[test]
+fn test_compatibility_unknown_value_rejected() {
+    let json = r#"{
+        "compatibility": "whatever",
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ]
+    }"#;
+    assert_eq!(parse_json(json), Err(Category::Data));
+}
+
+#[test]
+fn test_compatibility_round_trips_through_json_and_toml() {
+    let mut config = Config {
+        handled_fs: AccessFs::Execute.into(),
+        ..Default::default()
+    };
+    config.compatibility = crate::CompatLevel::SoftRequirement;
+
+    let json = config.to_json_string().unwrap();
+    assert!(json.contains("soft_requirement"));
+    assert_eq!(Config::parse_json(json.as_bytes()).unwrap(), config);
+
+    let toml = config.to_toml_string().unwrap();
+    assert!(toml.contains("soft_requirement"));
+    assert_eq!(Config::parse_toml(&toml).unwrap(), config);
+}
+
 #[test]
 fn test_all_versions_abi_read_execute() {
     for version in 1..=(LATEST_ABI as i32) {
@@ -227,7 +300,7 @@ fn test_all_versions_abi_read_execute() {
             AccessFs::from_read(abi) | (AccessFs::from_all(abi) & AccessFs::Refer);
 
         let config = Config {
-            abi: Some(abi),
+            abi: Some(AbiRequirement::Exact(abi)),
             handled_fs: expected_access,
             rules_path_beneath: [(TemplateString::from_text("."), expected_access)].into(),
             ..Default::default()
@@ -274,7 +347,7 @@ fn test_all_versions_abi_read_write() {
         let expected_access = AccessFs::from_all(abi) & !AccessFs::Execute;
 
         let config = Config {
-            abi: Some(abi),
+            abi: Some(AbiRequirement::Exact(abi)),
             handled_fs: expected_access,
             rules_path_beneath: [(TemplateString::from_text("."), expected_access)].into(),
             ..Default::default()
@@ -354,7 +427,7 @@ fn test_i32() {
     // currently greatest ABI.
     assert!(abi >= LATEST_ABI);
     let config = Config {
-        abi: Some(abi),
+        abi: Some(AbiRequirement::Exact(abi)),
         handled_fs: AccessFs::from_all(abi),
         ..Default::default()
     };
@@ -431,6 +504,136 @@ fn test_p64() {
     assert!(parse_toml(toml).is_err());
 }
 
+#[test]
+fn test_abi_latest() {
+    let json = r#"{
+        "abi": "latest",
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ]
+    }"#;
+    let toml = r#"
+        abi = "latest"
+        [[ruleset]]
+        handled_access_fs = [
+            "execute",
+        ]
+    "#;
+
+    let config = parse_json(json).unwrap();
+    assert_eq!(config, parse_toml(toml).unwrap());
+    // Mirrors test_i32: don't require perfect syncing with the Landlock
+    // crate's own notion of its highest known ABI.
+    match config.abi {
+        Some(AbiRequirement::Exact(abi)) => assert!(abi >= LATEST_ABI),
+        other => panic!("expected AbiRequirement::Exact(_), got {other:?}"),
+    }
+}
+
+#[test]
+fn test_abi_unknown_keyword_rejected() {
+    let json = r#"{
+        "abi": "oldest",
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ]
+    }"#;
+    let toml = r#"
+        abi = "oldest"
+        [[ruleset]]
+        handled_access_fs = [
+            "execute",
+        ]
+    "#;
+
+    assert_eq!(parse_json(json), Err(Category::Data));
+    assert!(parse_toml(toml).is_err());
+}
+
+#[test]
+fn test_abi_range_clamps_to_intersection() {
+    // `truncate` only exists from ABI::V3 onward, so a { min: 1, max: 6 }
+    // range must clamp it away: the intersection of every ABI in the range
+    // is exactly what the lowest one (V1) supports.
+    let json = r#"{
+        "abi": { "min": 1, "max": 6 },
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute", "truncate" ]
+            }
+        ]
+    }"#;
+    let toml = r#"
+        abi = { min = 1, max = 6 }
+        [[ruleset]]
+        handled_access_fs = [
+            "execute",
+            "truncate",
+        ]
+    "#;
+
+    let config = Config {
+        abi: Some(AbiRequirement::Range {
+            min: ABI::V1,
+            max: ABI::V6,
+        }),
+        handled_fs: AccessFs::Execute.into(),
+        ..Default::default()
+    };
+    assert_eq!(parse_json(json).unwrap(), config);
+    assert_eq!(parse_toml(toml).unwrap(), config);
+}
+
+#[test]
+fn test_abi_range_min_greater_than_max_rejected() {
+    let json = r#"{
+        "abi": { "min": 6, "max": 1 },
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ]
+    }"#;
+    let toml = r#"
+        abi = { min = 6, max = 1 }
+        [[ruleset]]
+        handled_access_fs = [
+            "execute",
+        ]
+    "#;
+
+    // min > max isn't expressible as a JSON Schema constraint here, so this
+    // is a parser-only rejection; see test_dup_abi.
+    assert_eq!(parse_json_schema(json, false), Err(Category::Data));
+    assert!(parse_toml(toml).is_err());
+}
+
+#[test]
+fn test_abi_range_missing_field_rejected() {
+    let json = r#"{
+        "abi": { "min": 1 },
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ]
+    }"#;
+    let toml = r#"
+        abi = { min = 1 }
+        [[ruleset]]
+        handled_access_fs = [
+            "execute",
+        ]
+    "#;
+
+    assert_eq!(parse_json(json), Err(Category::Data));
+    assert!(parse_toml(toml).is_err());
+}
+
 #[test]
 fn test_p128() {
     // 2^128
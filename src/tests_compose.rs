@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use crate::{config::ResolvedConfig, tests_helpers::parse_json, Config};
+use crate::{config::ResolvedConfig, tests_helpers::parse_json, ComposeMode, Config};
 use landlock::{Access, AccessFs, AccessNet, Scope, ABI};
 use std::path::PathBuf;
 
@@ -366,3 +366,116 @@ fn test_compose_same_resolved_path() {
         }
     );
 }
+
+fn test_idempotence_union(config: &Config) {
+    let mut bkp = config.clone();
+    bkp.compose_with(config, ComposeMode::Union);
+    assert_eq!(bkp, *config);
+}
+
+fn get_union(json1: &str, json2: &str) -> ResolvedConfig {
+    let j1 = parse_json(json1).unwrap();
+    test_idempotence_union(&j1);
+
+    let j2 = parse_json(json2).unwrap();
+    test_idempotence_union(&j2);
+
+    let mut c1 = j1.clone();
+    c1.compose_with(&j2, ComposeMode::Union);
+    test_idempotence_union(&c1);
+
+    let mut c2 = j2.clone();
+    c2.compose_with(&j1, ComposeMode::Union);
+    test_idempotence_union(&c2);
+
+    // Test commutativity
+    assert_eq!(c1, c2);
+
+    c1.resolve().unwrap()
+}
+
+#[test]
+fn test_compose_union_keeps_both_rules_unlike_intersect() {
+    let json1 = r#"{
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "a" ]
+            }
+        ]
+    }"#;
+    let json2 = r#"{
+        "ruleset": [
+            {
+                "handledAccessFs": [ "read_file" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "read_file" ],
+                "parent": [ "b" ]
+            }
+        ]
+    }"#;
+
+    // Unlike test_compose_exclusive (the same shape composed with the
+    // default intersect mode), union keeps both rights and both rules
+    // instead of dropping everything neither side handled.
+    assert_eq!(
+        get_union(json1, json2),
+        ResolvedConfig {
+            handled_fs: AccessFs::Execute | AccessFs::ReadFile,
+            rules_path_beneath: [
+                (PathBuf::from("a"), AccessFs::Execute.into()),
+                (PathBuf::from("b"), AccessFs::ReadFile.into()),
+            ]
+            .into(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_compose_union_ors_shared_rule_access() {
+    let json1 = r#"{
+        "ruleset": [
+            {
+                "handledAccessFs": [ "execute" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "a" ]
+            }
+        ]
+    }"#;
+    let json2 = r#"{
+        "ruleset": [
+            {
+                "handledAccessFs": [ "read_file" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "read_file" ],
+                "parent": [ "a" ]
+            }
+        ]
+    }"#;
+
+    assert_eq!(
+        get_union(json1, json2),
+        ResolvedConfig {
+            handled_fs: AccessFs::Execute | AccessFs::ReadFile,
+            rules_path_beneath: [(PathBuf::from("a"), AccessFs::Execute | AccessFs::ReadFile)]
+                .into(),
+            ..Default::default()
+        }
+    );
+}
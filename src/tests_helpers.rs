@@ -1,32 +1,18 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use crate::Config;
+use crate::{schema, Config};
 use landlock::ABI;
 use serde_json::error::Category;
 use serde_json::Value;
-use std::path::PathBuf;
-use std::{env, fs};
 
 pub(crate) const LATEST_ABI: ABI = ABI::V6;
 
-lazy_static! {
-    static ref JSON_VALIDATOR: jsonschema::Validator = {
-        let crate_dir = PathBuf::from(
-            env::var("CARGO_MANIFEST_DIR")
-                .expect("The environment variable CARGO_MANIFEST_DIR is not set"),
-        );
-        let schema_path = crate_dir.join("schema/landlockconfig.json");
-        let schema_str =
-            fs::read_to_string(schema_path).expect("Failed to read the JSON schema file");
-        let schema: Value = serde_json::from_str(&schema_str).expect("Invalid JSON");
-        jsonschema::validator_for(&schema).expect("Invalid JSON schema")
-    };
-}
-
 pub(crate) fn validate_json(json: &str) -> Result<(), ()> {
     let json = serde_json::from_str::<Value>(json).expect("Invalid JSON");
-    JSON_VALIDATOR.validate(&json).map(|_| ()).map_err(|e| {
-        eprintln!("JSON schema validation error: {e}");
+    schema::validate_config(&json).map_err(|errors| {
+        for error in errors {
+            eprintln!("JSON schema validation error: {error}");
+        }
     })
 }
 
@@ -49,9 +35,26 @@ pub(crate) fn parse_json(json: &str) -> Result<Config, Category> {
     parsing
 }
 
+pub(crate) fn validate_toml(toml: &str) -> Result<(), ()> {
+    let value = toml::from_str::<toml::Value>(toml).expect("Invalid TOML");
+    let json = serde_json::to_value(value).expect("TOML value is not representable as JSON");
+    schema::validate_config(&json).map_err(|errors| {
+        for error in errors {
+            eprintln!("JSON schema validation error: {error}");
+        }
+    })
+}
+
 pub(crate) fn parse_toml(toml: &str) -> Result<Config, toml::de::Error> {
-    Config::parse_toml(toml).map_err(|e| {
+    let parsing = Config::parse_toml(toml).map_err(|e| {
         eprintln!("TOML parsing error: {e}");
         e
-    })
+    });
+
+    // Ensures the JSON schema is consistent and stays up-to-date with the crate.
+    let valid = validate_toml(toml);
+    if parsing.is_ok() != valid.is_ok() {
+        panic!("Inconsistent validation: parser and schema validator disagree");
+    }
+    parsing
 }
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use crate::config::ResolvedConfig;
+use crate::parser::{PortRange, TemplateString};
 use crate::tests_helpers::{parse_json, parse_toml, validate_json, LATEST_ABI};
 use crate::Config;
 use landlock::{Access, AccessFs, AccessNet, Scope, ABI};
@@ -419,6 +420,115 @@ fn test_unknown_handled_access_fs_2() {
     assert_eq!(parse_json(json), Err(Category::Data));
 }
 
+#[test]
+fn test_include_exclude_handled_access_fs() {
+    let json = format!(
+        r#"{{
+            "abi": {},
+            "ruleset": [
+                {{
+                    "handledAccessFs": [
+                        {{ "include": [ "abi.read_write" ], "exclude": [ "make_sock", "make_fifo" ] }}
+                    ]
+                }}
+            ]
+        }}"#,
+        LATEST_ABI as u32
+    );
+    let expected_access = (AccessFs::from_all(LATEST_ABI) & !AccessFs::Execute)
+        & !(AccessFs::MakeSock | AccessFs::MakeFifo);
+    assert_eq!(
+        parse_json(&json),
+        Ok(Config {
+            abi: Some(crate::AbiRequirement::Exact(LATEST_ABI)),
+            handled_fs: expected_access,
+            ..Default::default()
+        }),
+    );
+}
+
+#[test]
+fn test_include_exclude_without_exclude() {
+    let json = r#"{
+        "ruleset": [
+            {
+                "handledAccessFs": [
+                    { "include": [ "execute", "write_file" ] }
+                ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json),
+        Ok(Config {
+            handled_fs: AccessFs::Execute | AccessFs::WriteFile,
+            ..Default::default()
+        }),
+    );
+}
+
+#[test]
+fn test_include_exclude_missing_abi() {
+    let json = r#"{
+        "ruleset": [
+            {
+                "handledAccessFs": [
+                    { "include": [ "abi.read_write" ] }
+                ]
+            }
+        ]
+    }"#;
+    assert_eq!(parse_json(json), Err(Category::Data));
+}
+
+#[test]
+fn test_include_exclude_unknown_field() {
+    let json = r#"{
+        "ruleset": [
+            {
+                "handledAccessFs": [
+                    { "include": [ "execute" ], "bogus": true }
+                ]
+            }
+        ]
+    }"#;
+    assert_eq!(parse_json(json), Err(Category::Data));
+}
+
+#[test]
+fn test_include_exclude_path_beneath() {
+    let json = format!(
+        r#"{{
+            "abi": {},
+            "ruleset": [
+                {{
+                    "handledAccessFs": [ "abi.read_write" ]
+                }}
+            ],
+            "pathBeneath": [
+                {{
+                    "allowedAccess": [
+                        {{ "include": [ "abi.read_write" ], "exclude": [ "make_sock" ] }}
+                    ],
+                    "parent": [ "/tmp" ]
+                }}
+            ]
+        }}"#,
+        LATEST_ABI as u32
+    );
+    let read_write = AccessFs::from_all(LATEST_ABI) & !AccessFs::Execute;
+    let access = read_write & !AccessFs::MakeSock;
+    assert_eq!(
+        parse_json(&json),
+        Ok(Config {
+            abi: Some(crate::AbiRequirement::Exact(LATEST_ABI)),
+            handled_fs: read_write,
+            rules_path_beneath: [(TemplateString::from_text("/tmp"), access)].into(),
+            ..Default::default()
+        }),
+    );
+}
+
 #[test]
 fn test_one_path_beneath_str() {
     let json = r#"{
@@ -723,7 +833,7 @@ fn test_one_net_port() {
         parse_json(json),
         Ok(Config {
             handled_net: AccessNet::BindTcp.into(),
-            rules_net_port: [(443, AccessNet::BindTcp.into())].into(),
+            rules_net_port: [(PortRange::single(443), AccessNet::BindTcp.into())].into(),
             ..Default::default()
         }),
     );
@@ -756,7 +866,11 @@ fn test_overlap_net_port() {
         parse_json(json),
         Ok(Config {
             handled_net: AccessNet::BindTcp | AccessNet::ConnectTcp,
-            rules_net_port: [(443, AccessNet::BindTcp | AccessNet::ConnectTcp)].into(),
+            rules_net_port: [(
+                PortRange::single(443),
+                AccessNet::BindTcp | AccessNet::ConnectTcp,
+            )]
+            .into(),
             ..Default::default()
         }),
     );
@@ -939,7 +1053,7 @@ fn test_infer_mixed_handled_and_rule() {
         Ok(Config {
             handled_fs: AccessFs::Execute.into(),
             handled_net: AccessNet::BindTcp.into(),
-            rules_net_port: [(443, AccessNet::BindTcp.into())].into(),
+            rules_net_port: [(PortRange::single(443), AccessNet::BindTcp.into())].into(),
             ..Default::default()
         }),
     );
@@ -994,7 +1108,7 @@ fn test_infer_handled_access_net() {
         parse_json(json),
         Ok(Config {
             handled_net: AccessNet::BindTcp | AccessNet::ConnectTcp,
-            rules_net_port: [(443, AccessNet::ConnectTcp.into())].into(),
+            rules_net_port: [(PortRange::single(443), AccessNet::ConnectTcp.into())].into(),
             ..Default::default()
         })
     );
@@ -1034,7 +1148,7 @@ fn test_net_port_alone() {
         parse_json(json),
         Ok(Config {
             handled_net: AccessNet::BindTcp.into(),
-            rules_net_port: [(443, AccessNet::BindTcp.into())].into(),
+            rules_net_port: [(PortRange::single(443), AccessNet::BindTcp.into())].into(),
             ..Default::default()
         })
     );
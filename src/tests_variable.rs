@@ -1,15 +1,15 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use crate::{
-    config::ResolvedConfig,
-    parser::{TemplateString, TemplateToken},
+    config::{ConfigError, ParseJsonError, ResolvedConfig, VariableSource},
+    parser::{TemplateParseError, TemplateString, TemplateToken},
     tests_helpers::{parse_json, parse_json_schema, parse_toml},
-    variable::{Name, ResolveError, Variables},
+    variable::{ExpandError, Name, ResolveError, Variables},
     Config,
 };
 use landlock::AccessFs;
 use serde_json::error::Category;
-use std::{path::PathBuf, str::FromStr};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
 #[test]
 fn test_empty_variable() {
@@ -276,9 +276,34 @@ fn test_one_variable_template_missing() {
     }"#;
     assert_eq!(
         parse_json(json).unwrap().resolve(),
-        Err(ResolveError::VariableNotFound(
-            Name::from_str("bar").unwrap()
-        )),
+        Err(ResolveError::VariableNotFound {
+            name: Name::from_str("bar").unwrap(),
+            span: Some(7..13),
+        }),
+    );
+}
+
+#[test]
+fn test_resolve_error_snippet() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "foo",
+                "literal": [ "a", "b" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo}/${bar}" ]
+            }
+        ]
+    }"#;
+    let err = parse_json(json).unwrap().resolve().unwrap_err();
+    assert_eq!(err.span(), Some(7..13));
+    assert_eq!(
+        err.snippet("${foo}/${bar}"),
+        Some("${foo}/${bar}\n       ^^^^^^".to_string())
     );
 }
 
@@ -348,7 +373,10 @@ fn test_one_variable_json_toml_template() {
         variables: Variables::try_from([("foo", vec!["a", "b"])]).unwrap(),
         handled_fs: AccessFs::Execute.into(),
         rules_path_beneath: [(
-            TemplateString(vec![TemplateToken::Var(Name::from_str("foo").unwrap())]),
+            TemplateString(vec![TemplateToken::var(
+                Name::from_str("foo").unwrap(),
+                (0, 6),
+            )]),
             AccessFs::Execute.into(),
         )]
         .into(),
@@ -411,9 +439,9 @@ fn test_two_variable_template() {
             rules_path_beneath: [(
                 TemplateString(vec![
                     TemplateToken::Text("before/".into()),
-                    TemplateToken::Var(Name::from_str("foo").unwrap()),
+                    TemplateToken::var(Name::from_str("foo").unwrap(), (7, 13)),
                     TemplateToken::Text("/".into()),
-                    TemplateToken::Var(Name::from_str("bar").unwrap()),
+                    TemplateToken::var(Name::from_str("bar").unwrap(), (14, 20)),
                     TemplateToken::Text("/after".into())
                 ]),
                 AccessFs::Execute.into()
@@ -440,6 +468,91 @@ fn test_two_variable_template() {
     );
 }
 
+#[test]
+fn test_resolve_with_limits_under_limit() {
+    let json = r#"{
+        "variable": [
+            { "name": "foo", "literal": [ "a", "b" ] }
+        ],
+        "pathBeneath": [
+            { "allowedAccess": [ "execute" ], "parent": [ "${foo}" ] }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve_with_limits(2),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [
+                (PathBuf::from("a"), AccessFs::Execute.into()),
+                (PathBuf::from("b"), AccessFs::Execute.into()),
+            ]
+            .into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_resolve_with_limits_over_limit() {
+    let json = r#"{
+        "variable": [
+            { "name": "foo", "literal": [ "a", "b" ] }
+        ],
+        "pathBeneath": [
+            { "allowedAccess": [ "execute" ], "parent": [ "${foo}" ] }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve_with_limits(1),
+        Err(ResolveError::TooManyCombinations {
+            estimated: 2,
+            limit: 1
+        })
+    );
+}
+
+#[test]
+fn test_resolve_with_limits_multiplies_across_variables() {
+    let json = r#"{
+        "variable": [
+            { "name": "foo", "literal": [ "a", "b" ] },
+            { "name": "bar", "literal": [ "X", "Y", "Z" ] }
+        ],
+        "pathBeneath": [
+            { "allowedAccess": [ "execute" ], "parent": [ "${foo}/${bar}" ] }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve_with_limits(5),
+        Err(ResolveError::TooManyCombinations {
+            estimated: 6,
+            limit: 5
+        })
+    );
+}
+
+#[test]
+fn test_variable_template_path_normalization_dedup() {
+    let json = r#"{
+        "variable": [
+            { "name": "foo", "literal": [ "a/", "a" ] }
+        ],
+        "pathBeneath": [
+            { "allowedAccess": [ "execute" ], "parent": [ "${foo}/b" ] },
+            { "allowedAccess": [ "read_file" ], "parent": [ "./a/b" ] }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute | AccessFs::ReadFile,
+            rules_path_beneath: [(PathBuf::from("a/b"), AccessFs::Execute | AccessFs::ReadFile)]
+                .into(),
+            ..Default::default()
+        })
+    );
+}
+
 #[test]
 fn test_special_characters() {
     let json = r#"{
@@ -476,3 +589,730 @@ fn test_special_characters() {
         })
     );
 }
+
+#[test]
+fn test_env_var_template() {
+    // SAFETY: this test does not run concurrently with anything else reading
+    // this variable.
+    unsafe {
+        std::env::set_var("LANDLOCKCONFIG_TEST_ENV_VAR", "/from/env");
+    }
+
+    let json = r#"{
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${env:LANDLOCKCONFIG_TEST_ENV_VAR}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/from/env"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_env_var_template_not_found() {
+    let json = r#"{
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${env:LANDLOCKCONFIG_TEST_ENV_VAR_MISSING}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Err(ResolveError::EnvNotFound {
+            name: Name::from_str("LANDLOCKCONFIG_TEST_ENV_VAR_MISSING").unwrap(),
+            span: Some(0..42),
+        })
+    );
+}
+
+#[test]
+fn test_env_var_template_injected() {
+    let config = parse_json(
+        r#"{
+            "pathBeneath": [
+                {
+                    "allowedAccess": [ "execute" ],
+                    "parent": [ "${env:HOME}" ]
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let template = TemplateString::tokenize("${env:HOME}").unwrap();
+    let env: std::collections::BTreeMap<String, String> =
+        [("HOME".to_string(), "/from/injected/env".to_string())].into();
+    assert_eq!(
+        config
+            .variables
+            .resolve_with_env(&template, &|name| env.get(name).cloned()),
+        Ok(vec![["/from/injected/env".to_string()].into()])
+    );
+}
+
+#[test]
+fn test_env_var_template_injected_default_fallback() {
+    let config = parse_json(r#"{}"#).unwrap();
+    let template = TemplateString::tokenize("${env:HOME:-/default/home}").unwrap();
+    assert_eq!(
+        config.variables.resolve_with_env(&template, &|_| None),
+        Ok(vec![["/default/home".to_string()].into()])
+    );
+}
+
+#[test]
+fn test_variable_template_default_used_when_unset() {
+    let json = r#"{
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo:-/default/path}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/default/path"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_template_default_ignored_when_set() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "foo",
+                "literal": [ "/set/path" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo:-/default/path}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/set/path"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_template_default_references_variable() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "bar",
+                "literal": [ "/from/bar" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo:-${bar}}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/from/bar"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_template_required_when_unset() {
+    let json = r#"{
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo:?foo must be set}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Err(ResolveError::Required("foo must be set".to_string()))
+    );
+}
+
+#[test]
+fn test_variable_template_required_ignored_when_set() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "foo",
+                "literal": [ "/set/path" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo:?foo must be set}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/set/path"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_template_alt_used_when_set() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "foo",
+                "literal": [ "/set/path" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo:+/alt/path}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/alt/path"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_template_alt_empty_when_unset() {
+    let json = r#"{
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo:+/alt/path}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_template_alt_references_variable() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "foo",
+                "literal": [ "/set/path" ]
+            },
+            {
+                "name": "bar",
+                "literal": [ "/from/bar" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo:+${bar}}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/from/bar"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_literal_references_another_variable() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "bar",
+                "literal": [ "a", "b" ]
+            },
+            {
+                "name": "foo",
+                "literal": [ "${bar}/bin" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [
+                (PathBuf::from("a/bin"), AccessFs::Execute.into()),
+                (PathBuf::from("b/bin"), AccessFs::Execute.into()),
+            ]
+            .into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_literal_references_chain_of_variables() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "baz",
+                "literal": [ "/root" ]
+            },
+            {
+                "name": "bar",
+                "literal": [ "${baz}/bar" ]
+            },
+            {
+                "name": "foo",
+                "literal": [ "${bar}/foo" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/root/bar/foo"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_variable_literal_direct_cycle() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "foo",
+                "literal": [ "${foo}" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Err(ResolveError::CyclicVariable(Name::from_str("foo").unwrap()))
+    );
+}
+
+#[test]
+fn test_variable_literal_indirect_cycle() {
+    let json = r#"{
+        "variable": [
+            {
+                "name": "foo",
+                "literal": [ "${bar}" ]
+            },
+            {
+                "name": "bar",
+                "literal": [ "${foo}" ]
+            }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${foo}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Err(ResolveError::CyclicVariable(Name::from_str("foo").unwrap()))
+    );
+}
+
+#[test]
+fn test_parse_json_with_env_fills_missing_literal() {
+    // SAFETY: this test does not run concurrently with anything else reading
+    // this variable.
+    unsafe {
+        std::env::set_var("LANDLOCKCONFIG_TEST_VAR_FOO", "/from/env/a:/from/env/b");
+    }
+
+    let json = r#"{
+        "variable": [
+            { "name": "LANDLOCKCONFIG_TEST_VAR_FOO" }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${LANDLOCKCONFIG_TEST_VAR_FOO}" ]
+            }
+        ]
+    }"#;
+    let config = Config::parse_json_with_env(json.as_bytes()).unwrap();
+    assert_eq!(
+        config.resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [
+                (PathBuf::from("/from/env/a"), AccessFs::Execute.into()),
+                (PathBuf::from("/from/env/b"), AccessFs::Execute.into()),
+            ]
+            .into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_parse_json_with_env_missing_env_var() {
+    let json = r#"{
+        "variable": [
+            { "name": "LANDLOCKCONFIG_TEST_VAR_MISSING_ENTIRELY" }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${LANDLOCKCONFIG_TEST_VAR_MISSING_ENTIRELY}" ]
+            }
+        ]
+    }"#;
+    assert!(matches!(
+        Config::parse_json_with_env(json.as_bytes()),
+        Err(ParseJsonError::Config(ConfigError::Resolve(
+            ResolveError::EnvVarNotFound(_)
+        )))
+    ));
+}
+
+#[test]
+fn test_parse_json_without_env_leaves_literal_empty() {
+    // Without the opt-in `_with_env` entry point, an absent `literal` stays
+    // an empty set (see `test_without_value`), so a template referencing it
+    // resolves to zero paths instead of reaching into the environment.
+    let json = r#"{
+        "variable": [
+            { "name": "LANDLOCKCONFIG_TEST_VAR_FOO" }
+        ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${LANDLOCKCONFIG_TEST_VAR_FOO}" ]
+            }
+        ]
+    }"#;
+    assert_eq!(
+        parse_json(json).unwrap().resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_parse_toml_with_env_fills_missing_literal() {
+    // SAFETY: this test does not run concurrently with anything else reading
+    // this variable.
+    unsafe {
+        std::env::set_var("LANDLOCKCONFIG_TEST_VAR_BAR", "/from/toml/env");
+    }
+
+    let toml = r#"
+        [[variable]]
+        name = "LANDLOCKCONFIG_TEST_VAR_BAR"
+
+        [[path_beneath]]
+        allowed_access = [ "execute" ]
+        parent = [ "${LANDLOCKCONFIG_TEST_VAR_BAR}" ]
+    "#;
+    let config = Config::parse_toml_with_env(toml).unwrap();
+    assert_eq!(
+        config.resolve(),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/from/toml/env"), AccessFs::Execute.into())]
+                .into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_expand_plain_text() {
+    let template = TemplateString::tokenize("/usr/bin").unwrap();
+    assert_eq!(template.expand(&HashMap::new()), Ok("/usr/bin".to_string()));
+}
+
+#[test]
+fn test_expand_variable_from_map() {
+    let template = TemplateString::tokenize("${HOME}/app").unwrap();
+    let vars = [("HOME".to_string(), "/home/user".to_string())].into();
+    assert_eq!(template.expand(&vars), Ok("/home/user/app".to_string()));
+}
+
+#[test]
+fn test_expand_variable_not_found() {
+    let template = TemplateString::tokenize("${MISSING}").unwrap();
+    assert_eq!(
+        template.expand(&HashMap::new()),
+        Err(ExpandError::VariableNotFound {
+            name: Name::from_str("MISSING").unwrap(),
+            span: Some(0..10),
+        })
+    );
+}
+
+#[test]
+fn test_expand_env_fallback() {
+    // SAFETY: this test does not run concurrently with anything else reading
+    // this variable.
+    unsafe {
+        std::env::set_var("LANDLOCKCONFIG_TEST_EXPAND_ENV_VAR", "/from/env");
+    }
+    let template = TemplateString::tokenize("${env:LANDLOCKCONFIG_TEST_EXPAND_ENV_VAR}").unwrap();
+    assert_eq!(
+        template.expand(&HashMap::new()),
+        Ok("/from/env".to_string())
+    );
+}
+
+#[test]
+fn test_expand_default_used_when_unset() {
+    let template = TemplateString::tokenize("${XDG_RUNTIME_DIR:-/tmp}/app").unwrap();
+    assert_eq!(template.expand(&HashMap::new()), Ok("/tmp/app".to_string()));
+}
+
+#[test]
+fn test_expand_required_when_unset() {
+    let template = TemplateString::tokenize("${XDG_RUNTIME_DIR:?must be set}").unwrap();
+    assert_eq!(
+        template.expand(&HashMap::new()),
+        Err(ExpandError::Required("must be set".to_string()))
+    );
+}
+
+#[test]
+fn test_expand_join() {
+    let template = TemplateString::tokenize("${join(${a}, ${b})}").unwrap();
+    let vars = [
+        ("a".to_string(), "/home/user".to_string()),
+        ("b".to_string(), "app".to_string()),
+    ]
+    .into();
+    assert_eq!(template.expand(&vars), Ok("/home/user/app".to_string()));
+}
+
+#[test]
+fn test_expand_join_collapses_doubled_separator() {
+    let template = TemplateString::tokenize("${join(${a}, ${b})}").unwrap();
+    let vars = [
+        ("a".to_string(), "/home/user/".to_string()),
+        ("b".to_string(), "/app".to_string()),
+    ]
+    .into();
+    assert_eq!(template.expand(&vars), Ok("/home/user/app".to_string()));
+}
+
+#[test]
+fn test_expand_regex_replace() {
+    let template = TemplateString::tokenize("${regex_replace(${path}, user, guest)}").unwrap();
+    let vars = [("path".to_string(), "/home/user/app".to_string())].into();
+    assert_eq!(template.expand(&vars), Ok("/home/guest/app".to_string()));
+}
+
+#[test]
+fn test_join_requires_at_least_two_arguments() {
+    assert_eq!(
+        TemplateString::tokenize("${join(${a})}"),
+        Err(TemplateParseError::InvalidCall {
+            offset: 0,
+            message: "join() expects at least 2 arguments, found 1".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_regex_replace_requires_exactly_three_arguments() {
+    assert_eq!(
+        TemplateString::tokenize("${regex_replace(${a}, b)}"),
+        Err(TemplateParseError::InvalidCall {
+            offset: 0,
+            message: "regex_replace() expects exactly 3 arguments, found 2".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_unknown_function_rejected() {
+    assert_eq!(
+        TemplateString::tokenize("${uppercase(${a})}"),
+        Err(TemplateParseError::InvalidCall {
+            offset: 0,
+            message: "unknown function `uppercase`".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_call_missing_closing_brace_rejected() {
+    assert_eq!(
+        TemplateString::tokenize("${join(${a}, ${b})x"),
+        Err(TemplateParseError::InvalidCall {
+            offset: 0,
+            message: "expected '}' after ')'".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_call_round_trips_through_display() {
+    let template = TemplateString::tokenize("${join(${a}, b)}").unwrap();
+    assert_eq!(template.to_string(), "${join(${a}, b)}");
+}
+
+#[test]
+fn test_resolve_config_only_rejects_undeclared_variable() {
+    let json = r#"{
+        "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${LANDLOCKCONFIG_TEST_VAR_UNDECLARED}" ]
+            }
+        ]
+    }"#;
+    let config = parse_json(json).unwrap();
+    assert_eq!(
+        config.resolve_with_source(VariableSource::ConfigOnly),
+        Err(ResolveError::VariableNotFound {
+            name: Name::from_str("LANDLOCKCONFIG_TEST_VAR_UNDECLARED").unwrap(),
+            span: Some(0..37),
+        })
+    );
+}
+
+#[test]
+fn test_resolve_config_and_env_falls_back_to_allowlisted_env_var() {
+    // SAFETY: this test does not run concurrently with anything else reading
+    // this variable.
+    unsafe {
+        std::env::set_var(
+            "LANDLOCKCONFIG_LANDLOCKCONFIG_TEST_VAR_ENV_FALLBACK",
+            "/from/env/a:/from/env/b",
+        );
+    }
+
+    let json = r#"{
+        "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${LANDLOCKCONFIG_TEST_VAR_ENV_FALLBACK}" ]
+            }
+        ]
+    }"#;
+    let config = parse_json(json).unwrap();
+    assert_eq!(
+        config.resolve_with_source(VariableSource::ConfigAndEnv),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [
+                (PathBuf::from("/from/env/a"), AccessFs::Execute.into()),
+                (PathBuf::from("/from/env/b"), AccessFs::Execute.into()),
+            ]
+            .into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_resolve_config_and_env_still_prefers_config_variable() {
+    let json = r#"{
+        "variable": [
+            { "name": "LANDLOCKCONFIG_TEST_VAR_PREFERRED", "literal": [ "/from/config" ] }
+        ],
+        "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${LANDLOCKCONFIG_TEST_VAR_PREFERRED}" ]
+            }
+        ]
+    }"#;
+    let config = parse_json(json).unwrap();
+    assert_eq!(
+        config.resolve_with_source(VariableSource::ConfigAndEnv),
+        Ok(ResolvedConfig {
+            handled_fs: AccessFs::Execute.into(),
+            rules_path_beneath: [(PathBuf::from("/from/config"), AccessFs::Execute.into())].into(),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_resolve_config_and_env_reports_distinct_error_when_unset() {
+    let json = r#"{
+        "ruleset": [ { "handledAccessFs": [ "execute" ] } ],
+        "pathBeneath": [
+            {
+                "allowedAccess": [ "execute" ],
+                "parent": [ "${LANDLOCKCONFIG_TEST_VAR_ENV_ALSO_MISSING}" ]
+            }
+        ]
+    }"#;
+    let config = parse_json(json).unwrap();
+    assert_eq!(
+        config.resolve_with_source(VariableSource::ConfigAndEnv),
+        Err(ResolveError::VariableNotFoundInConfigOrEnv {
+            name: Name::from_str("LANDLOCKCONFIG_TEST_VAR_ENV_ALSO_MISSING").unwrap(),
+            span: Some(0..43),
+        })
+    );
+}
@@ -1,14 +1,22 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use crate::parser::{TemplateString, TemplateToken};
+use crate::config::VariableSource;
+use crate::diagnostic::caret_snippet;
+use crate::parser::{TemplateParseError, TemplateString, TemplateToken};
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     iter::Peekable,
+    ops::Range,
+    path::{Component, Path, PathBuf},
     str::FromStr,
 };
 use thiserror::Error;
 
+/// The namespaced prefix [`VariableSource::ConfigAndEnv`] looks an
+/// undeclared `${name}` up under, e.g. `LANDLOCKCONFIG_HOME` for `${HOME}`.
+const ENV_VAR_PREFIX: &str = "LANDLOCKCONFIG_";
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Name(String);
 
@@ -22,41 +30,289 @@ impl FromStr for Name {
     type Err = NameError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.is_empty() {
+        let Some((_, first)) = s.char_indices().next() else {
             return Err(NameError::Empty);
-        }
+        };
 
-        if !s.chars().next().unwrap().is_ascii_alphabetic() {
-            return Err(NameError::InvalidFirstCharacter(s.to_string()));
+        if !first.is_ascii_alphabetic() {
+            return Err(NameError::InvalidFirstCharacter {
+                found: first,
+                offset: 0,
+            });
         }
 
-        if !s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
-            return Err(NameError::InvalidCharacter(s.to_string()));
+        if let Some((offset, found)) = s
+            .char_indices()
+            .find(|&(_, c)| !(c.is_ascii_alphanumeric() || c == '_'))
+        {
+            return Err(NameError::InvalidCharacter { found, offset });
         }
 
         Ok(Self(s.to_string()))
     }
 }
 
+/// Byte offsets in these variants are relative to the name string itself
+/// (as passed to [`Name::from_str`]), not to any enclosing `${...}`
+/// template reference; the template tokenizer adds its own `${` offset on
+/// top, see [`crate::parser::TemplateParseError`].
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum NameError {
     #[error("name cannot be empty")]
     Empty,
-    #[error("invalid first character in name (must be ASCII alphabetic): {0}")]
-    InvalidFirstCharacter(String),
-    #[error("invalid character(s) in name (must be ASCII alphanumeric or '_'): {0}")]
-    InvalidCharacter(String),
+    #[error("invalid first character `{found}` at position {offset} (must be ASCII alphabetic)")]
+    InvalidFirstCharacter { found: char, offset: usize },
+    #[error(
+        "invalid character `{found}` at position {offset} (must be ASCII alphanumeric or '_')"
+    )]
+    InvalidCharacter { found: char, offset: usize },
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub(crate) struct Variables(BTreeMap<Name, BTreeSet<String>>);
 
+/// Tracks a variable's progress through [`Variables::resolve_variable`]'s
+/// DFS over `${...}` references nested in `variable.literal` values, so a
+/// reference that leads back to a variable still being expanded is reported
+/// as [`ResolveError::CyclicVariable`] instead of recursing forever.
+/// Variables never looked up during a given [`Variables::resolve`] call
+/// have no entry at all (treated the same as "not yet visited").
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ResolutionState {
+    InProgress,
+    Done,
+}
+
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum ResolveError {
-    #[error("variable '{0}' not found")]
-    VariableNotFound(Name),
+    /// `span` is the byte range of the offending `${name}` reference within
+    /// its original template literal, taken from the [`TemplateToken::Var`]
+    /// that failed to resolve, letting a caller render a caret snippet
+    /// pointing at it; `None` when there is no source template to point
+    /// into (e.g. the synthetic `abi` lookup in [`crate::parser`]'s access
+    /// group resolution).
+    #[error("variable '{name}' not found")]
+    VariableNotFound {
+        name: Name,
+        span: Option<Range<usize>>,
+    },
+    #[error("environment variable '{0}' not found")]
+    EnvVarNotFound(String),
+    /// Raised by a `${name}` reference under [`VariableSource::ConfigAndEnv`]
+    /// when `name` is declared neither in the document's `variable` section
+    /// nor as the allowlisted `LANDLOCKCONFIG_<NAME>` environment variable.
+    /// Unlike [`ResolveError::VariableNotFound`] (raised for the same
+    /// reference under the default [`VariableSource::ConfigOnly`]), this
+    /// tells the caller the environment was also consulted and still came
+    /// up empty.
+    #[error(
+        "variable '{name}' not found in config or as environment variable '{ENV_VAR_PREFIX}{name}'"
+    )]
+    VariableNotFoundInConfigOrEnv {
+        name: Name,
+        span: Option<Range<usize>>,
+    },
+    /// Raised by a `${env:NAME}` reference when `NAME` isn't set in the
+    /// process environment (or the substitute map passed to
+    /// [`Variables::resolve_with_env`]) and has no `:-default` fallback.
+    /// `span` points at the offending reference, like
+    /// [`ResolveError::VariableNotFound`].
+    #[error("environment variable '{name}' not found")]
+    EnvNotFound {
+        name: Name,
+        span: Option<Range<usize>>,
+    },
+    /// Raised by a `${var:?message}` reference when `var` is unset.
+    #[error("{0}")]
+    Required(String),
     #[error(transparent)]
     InvalidName(#[from] NameError),
+    /// A variable's own `literal` value contains a `${...}` reference back
+    /// to a variable that is still being expanded, e.g. `foo = ["${bar}"]`
+    /// and `bar = ["${foo}"]`. Raised by [`Variables::resolve`]'s
+    /// transitive expansion instead of recursing forever.
+    #[error("cyclic variable reference: '{0}'")]
+    CyclicVariable(Name),
+    /// A variable's `literal` value is not a well-formed template, e.g. an
+    /// unclosed `${`.
+    #[error("invalid template in variable value: {0}")]
+    InvalidTemplate(#[from] TemplateParseError),
+    /// The Cartesian product of a template's resolved `${...}` value sets
+    /// would exceed the `limit` passed to
+    /// [`Variables::resolve_with_limit`], e.g. `Config::resolve_with_limits`.
+    /// `estimated` is the product of each token's set cardinality,
+    /// saturating at [`u64::MAX`] rather than overflowing. Raised before any
+    /// combination is materialized, so a config can't be used to exhaust
+    /// memory or flood the kernel with rules.
+    #[error("resolving this template would produce {estimated} rules, over the limit of {limit}")]
+    TooManyCombinations { estimated: u64, limit: u64 },
+}
+
+impl ResolveError {
+    /// Byte range of the offending `${...}` reference within its original
+    /// template literal, when this error has one to point at. `None` for
+    /// [`ResolveError::EnvVarNotFound`] and [`ResolveError::Required`],
+    /// which aren't currently tracked back to a [`TemplateToken::Var`], and
+    /// for a [`ResolveError::VariableNotFound`] raised with no source
+    /// template at all.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::VariableNotFound { span, .. }
+            | Self::EnvNotFound { span, .. }
+            | Self::VariableNotFoundInConfigOrEnv { span, .. } => span.clone(),
+            Self::EnvVarNotFound(_)
+            | Self::Required(_)
+            | Self::InvalidName(_)
+            | Self::CyclicVariable(_)
+            | Self::InvalidTemplate(_)
+            | Self::TooManyCombinations { .. } => None,
+        }
+    }
+
+    /// Renders a one-line, caret-underlined snippet of `source` (the
+    /// original template literal this error was resolved from, e.g. its
+    /// `to_string()`) pointing at [`ResolveError::span`]. Returns `None` if
+    /// this error has no span.
+    pub fn snippet(&self, source: &str) -> Option<String> {
+        self.span().map(|span| caret_snippet(source, &span))
+    }
+}
+
+/// Errors from [`TemplateString::expand`], the simpler sibling of
+/// [`Variables::resolve`] for a call site that needs a single resolved
+/// string (e.g. a `path_beneath` parent) rather than the Cartesian product
+/// of a multi-valued `variable` section.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ExpandError {
+    /// `span` is the byte range of the offending `${name}` reference within
+    /// its original template literal, like [`ResolveError::VariableNotFound`].
+    #[error("variable '{name}' not found")]
+    VariableNotFound {
+        name: Name,
+        span: Option<Range<usize>>,
+    },
+    /// Raised by a `${env:NAME}` reference when `NAME` isn't set in the
+    /// process environment and has no `:-default` fallback.
+    #[error("environment variable '{name}' not found")]
+    EnvNotFound {
+        name: Name,
+        span: Option<Range<usize>>,
+    },
+    /// Raised by a `${var:?message}` reference when `var` is unset.
+    #[error("{0}")]
+    Required(String),
+}
+
+impl ExpandError {
+    /// Byte range of the offending `${...}` reference within its original
+    /// template literal, when this error has one to point at. `None` for
+    /// [`ExpandError::Required`].
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::VariableNotFound { span, .. } | Self::EnvNotFound { span, .. } => span.clone(),
+            Self::Required(_) => None,
+        }
+    }
+
+    /// Renders a one-line, caret-underlined snippet of `source` (the
+    /// original template literal this error was expanded from, e.g. its
+    /// `to_string()`) pointing at [`ExpandError::span`]. Returns `None` if
+    /// this error has no span.
+    pub fn snippet(&self, source: &str) -> Option<String> {
+        self.span().map(|span| caret_snippet(source, &span))
+    }
+}
+
+impl TemplateString {
+    /// Expands `self` into a single string, resolving each `${name}`
+    /// reference against `vars` and falling back to the process environment
+    /// for `${env:NAME}` references, so a config can describe
+    /// `${XDG_RUNTIME_DIR}/app` instead of a fixed path portable only on the
+    /// machine it was written on. Unlike [`Variables::resolve`] (built for
+    /// the `variable` section's Cartesian product of possible values), an
+    /// undefined reference here is a structured [`ExpandError`] rather than
+    /// an empty-string expansion. See [`crate::config::VariableSource`] for
+    /// how `${env:NAME}` relates to the crate's other two
+    /// environment-variable mechanisms.
+    pub fn expand(&self, vars: &HashMap<String, String>) -> Result<String, ExpandError> {
+        let mut out = String::new();
+        for token in &self.0 {
+            out.push_str(&expand_token(token, vars)?);
+        }
+        Ok(out)
+    }
+}
+
+fn expand_token(
+    token: &TemplateToken,
+    vars: &HashMap<String, String>,
+) -> Result<String, ExpandError> {
+    match token {
+        TemplateToken::Text(text) => Ok(text.clone()),
+        TemplateToken::Var {
+            name,
+            default,
+            required_msg,
+            alt,
+            ..
+        } => match vars.get(name.0.as_str()) {
+            Some(value) => match alt {
+                Some(alt) => alt.expand(vars),
+                None => Ok(value.clone()),
+            },
+            None => match (default, required_msg, alt) {
+                (Some(default), _, _) => default.expand(vars),
+                (None, Some(msg), _) => Err(ExpandError::Required(msg.clone())),
+                (None, None, Some(_)) => Ok(String::new()),
+                (None, None, None) => Err(ExpandError::VariableNotFound {
+                    name: name.clone(),
+                    span: token.span(),
+                }),
+            },
+        },
+        TemplateToken::Env { name, default, .. } => match std::env::var(&name.0) {
+            Ok(value) => Ok(value),
+            Err(_) => match default {
+                Some(default) => default.expand(vars),
+                None => Err(ExpandError::EnvNotFound {
+                    name: name.clone(),
+                    span: token.span(),
+                }),
+            },
+        },
+        TemplateToken::Join { args, .. } => {
+            let mut parts = args.iter().map(|arg| arg.expand(vars));
+            let mut joined = parts.next().transpose()?.unwrap_or_default();
+            for part in parts {
+                joined = join_path_segments(&joined, &part?);
+            }
+            Ok(joined)
+        }
+        TemplateToken::RegexReplace {
+            src,
+            pattern,
+            replacement,
+            ..
+        } => {
+            let src = src.expand(vars)?;
+            let pattern = pattern.expand(vars)?;
+            let replacement = replacement.expand(vars)?;
+            Ok(src.replace(pattern.as_str(), &replacement))
+        }
+    }
+}
+
+/// Joins two path segments with exactly one `/` between them, collapsing a
+/// doubled separator rather than producing `a//b`. Not [`Path::join`],
+/// which discards `a` entirely when `b` is absolute - surprising here,
+/// since both sides are already-resolved template values rather than a
+/// base path and a user-supplied suffix.
+fn join_path_segments(a: &str, b: &str) -> String {
+    match (a.ends_with('/'), b.starts_with('/')) {
+        (true, true) => format!("{a}{}", &b[1..]),
+        (true, false) | (false, true) => format!("{a}{b}"),
+        (false, false) => format!("{a}/{b}"),
+    }
 }
 
 impl Variables {
@@ -68,21 +324,193 @@ impl Variables {
     pub(crate) fn resolve(
         &self,
         template: &TemplateString,
+    ) -> Result<Vec<BTreeSet<String>>, ResolveError> {
+        self.resolve_with_limit(template, u64::MAX)
+    }
+
+    /// Like [`Variables::resolve`], but fails with
+    /// [`ResolveError::TooManyCombinations`] instead of letting
+    /// [`VecStringIterator`] materialize a template whose resolved
+    /// `${...}` value sets would produce more than `limit` combinations.
+    /// The product is estimated up front (saturating, so it can't
+    /// overflow) from each set's cardinality, before any combination is
+    /// built. See [`Config::resolve_with_limits`].
+    pub(crate) fn resolve_with_limit(
+        &self,
+        template: &TemplateString,
+        limit: u64,
+    ) -> Result<Vec<BTreeSet<String>>, ResolveError> {
+        self.resolve_with_limit_and_source(template, limit, VariableSource::ConfigOnly)
+    }
+
+    /// Like [`Variables::resolve_with_limit`], but also accepts `var_source`
+    /// to select whether an undeclared `${name}` may fall back to the
+    /// allowlisted `LANDLOCKCONFIG_<NAME>` environment variable. See
+    /// [`Config::resolve_with_source`].
+    pub(crate) fn resolve_with_limit_and_source(
+        &self,
+        template: &TemplateString,
+        limit: u64,
+        var_source: VariableSource,
+    ) -> Result<Vec<BTreeSet<String>>, ResolveError> {
+        let parts =
+            self.resolve_with_source(template, &|name| std::env::var(name).ok(), var_source)?;
+        let estimated = parts
+            .iter()
+            .fold(1u64, |acc, set| acc.saturating_mul(set.len() as u64));
+        if estimated > limit {
+            return Err(ResolveError::TooManyCombinations { estimated, limit });
+        }
+        Ok(parts)
+    }
+
+    /// Like [`Variables::resolve`], but looks up `${env:NAME}` references
+    /// through `env` instead of the real process environment. Exposed so
+    /// tests can cover `${env:NAME}` resolution with a substitute map
+    /// instead of touching real process environment variables.
+    pub(crate) fn resolve_with_env(
+        &self,
+        template: &TemplateString,
+        env: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Vec<BTreeSet<String>>, ResolveError> {
+        self.resolve_with_source(template, env, VariableSource::ConfigOnly)
+    }
+
+    /// Like [`Variables::resolve_with_env`], but also accepts `var_source`,
+    /// so tests can cover [`VariableSource::ConfigAndEnv`]'s fallback with a
+    /// substitute map instead of touching real process environment
+    /// variables.
+    pub(crate) fn resolve_with_source(
+        &self,
+        template: &TemplateString,
+        env: &dyn Fn(&str) -> Option<String>,
+        var_source: VariableSource,
+    ) -> Result<Vec<BTreeSet<String>>, ResolveError> {
+        let mut state = BTreeMap::new();
+        let mut cache = BTreeMap::new();
+        self.resolve_with(template, &mut state, &mut cache, env, var_source)
+    }
+
+    /// Resolves `template`, threading `state`/`cache` through every nested
+    /// `default`/`alt` and transitive variable lookup so a single top-level
+    /// [`Variables::resolve`] call shares one cycle check and one cache, and
+    /// `env` through every `${env:NAME}` lookup. See
+    /// [`Variables::resolve_variable`] for what `state` and `cache` track.
+    fn resolve_with(
+        &self,
+        template: &TemplateString,
+        state: &mut BTreeMap<Name, ResolutionState>,
+        cache: &mut BTreeMap<Name, BTreeSet<String>>,
+        env: &dyn Fn(&str) -> Option<String>,
+        var_source: VariableSource,
     ) -> Result<Vec<BTreeSet<String>>, ResolveError> {
         template
             .0
             .iter()
             .map(|token| match token {
                 TemplateToken::Text(text) => Ok([text.to_string()].into()),
-                TemplateToken::Var(name) => self
-                    .0
-                    .get(name)
-                    .cloned()
-                    .ok_or_else(|| ResolveError::VariableNotFound(name.clone())),
+                TemplateToken::Var {
+                    name,
+                    default,
+                    required_msg,
+                    alt,
+                    ..
+                } => match self.resolve_variable(name, state, cache, env, var_source)? {
+                    Some(values) => match alt {
+                        Some(alt) => {
+                            let parts = self.resolve_with(alt, state, cache, env, var_source)?;
+                            Ok(VecStringIterator::new(&parts).collect())
+                        }
+                        None => Ok(values),
+                    },
+                    None => match (default, required_msg, alt) {
+                        (Some(default), _, _) => {
+                            let parts =
+                                self.resolve_with(default, state, cache, env, var_source)?;
+                            Ok(VecStringIterator::new(&parts).collect())
+                        }
+                        (None, Some(msg), _) => Err(ResolveError::Required(msg.clone())),
+                        (None, None, Some(_)) => Ok(BTreeSet::new()),
+                        (None, None, None) => Err(if var_source == VariableSource::ConfigAndEnv {
+                            ResolveError::VariableNotFoundInConfigOrEnv {
+                                name: name.clone(),
+                                span: token.span(),
+                            }
+                        } else {
+                            ResolveError::VariableNotFound {
+                                name: name.clone(),
+                                span: token.span(),
+                            }
+                        }),
+                    },
+                },
+                TemplateToken::Env { name, default, .. } => match env(&name.0) {
+                    Some(value) => Ok([value].into()),
+                    None => match default {
+                        Some(default) => {
+                            let parts =
+                                self.resolve_with(default, state, cache, env, var_source)?;
+                            Ok(VecStringIterator::new(&parts).collect())
+                        }
+                        None => Err(ResolveError::EnvNotFound {
+                            name: name.clone(),
+                            span: token.span(),
+                        }),
+                    },
+                },
             })
             .collect()
     }
 
+    /// Looks up `name`'s stored `variable.literal` value set, expanding any
+    /// `${...}` references it itself contains (e.g. `foo = ["${bar}/bin"]`)
+    /// before returning it. `Ok(None)` means `name` has no stored value at
+    /// all, matching what [`Variables::resolve_with`]'s default/required/alt
+    /// fallback expects; a genuine cycle (`name` reached again while still
+    /// [`ResolutionState::InProgress`]) is the only case returning
+    /// [`ResolveError::CyclicVariable`]. `cache` memoizes each variable's
+    /// fully-resolved set so it is only expanded once per top-level
+    /// [`Variables::resolve`] call, however many times it's referenced.
+    /// Under [`VariableSource::ConfigAndEnv`], a `name` absent from the
+    /// document falls back to the `LANDLOCKCONFIG_<NAME>` environment
+    /// variable (looked up through `env`, split into the crate's existing
+    /// multi-value list form on `:`) before reporting `Ok(None)`.
+    fn resolve_variable(
+        &self,
+        name: &Name,
+        state: &mut BTreeMap<Name, ResolutionState>,
+        cache: &mut BTreeMap<Name, BTreeSet<String>>,
+        env: &dyn Fn(&str) -> Option<String>,
+        var_source: VariableSource,
+    ) -> Result<Option<BTreeSet<String>>, ResolveError> {
+        if let Some(values) = cache.get(name) {
+            return Ok(Some(values.clone()));
+        }
+        let Some(raw_values) = self.0.get(name) else {
+            if var_source == VariableSource::ConfigAndEnv {
+                if let Some(value) = env(&format!("{ENV_VAR_PREFIX}{name}")) {
+                    return Ok(Some(value.split(':').map(str::to_string).collect()));
+                }
+            }
+            return Ok(None);
+        };
+        if state.get(name) == Some(&ResolutionState::InProgress) {
+            return Err(ResolveError::CyclicVariable(name.clone()));
+        }
+        state.insert(name.clone(), ResolutionState::InProgress);
+
+        let mut resolved = BTreeSet::new();
+        for raw in raw_values {
+            let value_template = TemplateString::tokenize(raw)?;
+            let parts = self.resolve_with(&value_template, state, cache, env, var_source)?;
+            resolved.extend(VecStringIterator::new(&parts));
+        }
+
+        state.insert(name.clone(), ResolutionState::Done);
+        cache.insert(name.clone(), resolved.clone());
+        Ok(Some(resolved))
+    }
+
     pub(crate) fn iter(&self) -> std::collections::btree_map::Iter<'_, Name, BTreeSet<String>> {
         self.0.iter()
     }
@@ -154,12 +582,18 @@ impl<'a, T> VecStringIterator<'a, T> {
 
 /// Iterator that generates the Cartesian product of multiple sets, producing
 /// all possible combinations as concatenated strings in lexicographic order.
+///
+/// Different combinations can concatenate to filesystem paths that are
+/// distinct as strings but identical once cleaned (e.g. `a//b` and `a/b`,
+/// or `./x` and `x`); callers building `rules_path_beneath` from this
+/// iterator's output should normalize with [`lexically_normalize`] and
+/// deduplicate globally (a `BTreeMap`/`BTreeSet` keyed by the cleaned path)
+/// rather than assuming equal paths are adjacent, since normalization can
+/// reorder which concatenations compare equal.
 impl<'a, T> Iterator for VecStringIterator<'a, T>
 where
     T: AsRef<str> + 'a,
 {
-    // TODO: Use PathBuf, canonicalize, and filter to avoid consecutive returned paths to be the
-    // same (e.g. peekable + dedup).
     type Item = String;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -197,6 +631,31 @@ where
     }
 }
 
+/// Lexically cleans `path`: collapses `.` components and repeated
+/// separators, resolves a `..` against a preceding [`Component::Normal`]
+/// component, and drops a `..` that immediately follows the root (there's
+/// nothing above it to go to). Never touches the filesystem (unlike
+/// [`std::fs::canonicalize`]), so it works for paths that don't exist yet.
+/// A leading `..` in a relative path has nothing to resolve against and is
+/// kept as-is rather than treated as an error.
+pub(crate) fn lexically_normalize(path: &str) -> PathBuf {
+    let mut components: Vec<Component> = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match components.last() {
+                Some(Component::Normal(_)) => {
+                    components.pop();
+                }
+                Some(Component::RootDir) => {}
+                _ => components.push(component),
+            },
+            _ => components.push(component),
+        }
+    }
+    components.iter().collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -277,4 +736,34 @@ mod tests {
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn test_lexically_normalize_repeated_separators() {
+        assert_eq!(lexically_normalize("a//b"), PathBuf::from("a/b"));
+    }
+
+    #[test]
+    fn test_lexically_normalize_current_dir() {
+        assert_eq!(lexically_normalize("./x"), PathBuf::from("x"));
+        assert_eq!(lexically_normalize("a/./b"), PathBuf::from("a/b"));
+    }
+
+    #[test]
+    fn test_lexically_normalize_parent_dir() {
+        assert_eq!(lexically_normalize("a/b/../c"), PathBuf::from("a/c"));
+        assert_eq!(lexically_normalize("/a/b/../../c"), PathBuf::from("/c"));
+    }
+
+    #[test]
+    fn test_lexically_normalize_leading_parent_dir_kept() {
+        // Nothing to resolve `..` against, so it's kept as-is rather than
+        // dropped or treated as an error.
+        assert_eq!(lexically_normalize("../a"), PathBuf::from("../a"));
+        assert_eq!(lexically_normalize("/../a"), PathBuf::from("/a"));
+    }
+
+    #[test]
+    fn test_lexically_normalize_already_clean() {
+        assert_eq!(lexically_normalize("/a/b/c"), PathBuf::from("/a/b/c"));
+    }
 }